@@ -0,0 +1,361 @@
+use crate::testing::RenderedCanvas;
+use crate::{
+    terminal, CanvasItemPosition, CanvasLattice, ColorSupport, DrawError, DrawableUnit,
+    TerminalLattice, UnitStyle,
+};
+use data_structure::Pair;
+use std::io;
+
+/// キャンバスの描画内容の出力先を表す．
+///
+/// `Canvas::render_to`は，1フレームごとに`begin_frame`を呼び出した後，枠を含めたすべての点について行優先で`draw_cell`を呼び出し，最後に`end_frame`を呼び出す．
+/// 各点の位置は，枠の左上の角を`(0, 0)`とした描画単位の座標で与えられる．
+///
+/// 出力先を差し替えられるのは`Canvas::render_to`を用いて描画する場合のみである．
+/// `TerminalSession`，`FrameWriter`および`DiffRenderer`はこのトレイトを介さず，エスケープシーケンスを直接書き込む．
+pub trait Backend {
+    /// フレームの描画を開始する．
+    /// # Params
+    /// 1. `size` 枠を含めたフレームのサイズ．
+    fn begin_frame(&mut self, size: Pair<CanvasLattice>) -> Result<(), DrawError>;
+
+    /// 指定した点に描画単位を描画する．
+    /// 描画時のスタイルは`DrawableUnit::style`で得られる．
+    fn draw_cell(
+        &mut self,
+        position: CanvasItemPosition,
+        unit: DrawableUnit,
+    ) -> Result<(), DrawError>;
+
+    /// フレームの描画を終了し，描画内容を出力先に反映する．
+    fn end_frame(&mut self) -> Result<(), DrawError>;
+
+    /// この出力先に表示可能な領域のサイズを，描画単位を単位として返す．
+    /// サイズに制限がない場合や，サイズが不明な場合は`None`を返す．
+    fn size(&self) -> Option<Pair<CanvasLattice>>;
+}
+
+/// ANSIエスケープシーケンスに対応した端末に描画内容を出力する．
+///
+/// 各フレームは端末の左上から描画され，1フレームにつき1度だけ出力先に書き込まれ，フラッシュされる．
+///
+/// 表示可能な領域のサイズは，`stdout`で生成した標準出力へのバックエンドでのみ端末から取得される．
+#[derive(Debug)]
+pub struct AnsiBackend<W: io::Write> {
+    /// 出力先．
+    writer: W,
+    /// 出力先が標準出力であり，表示可能な領域のサイズを端末から取得できるか．
+    writes_to_terminal: bool,
+    /// 色の変換に用いる色表現範囲．
+    color_support: ColorSupport,
+    /// 描画中のフレームの内容．
+    buffer: String,
+    /// 描画中のフレームにおいて，最後に指定したスタイル．
+    current_style: UnitStyle,
+    /// 次に描画単位が書き込まれる位置．カーソル移動を省略するために用いる．
+    cursor: Option<CanvasItemPosition>,
+}
+
+/// 描画内容を，エスケープシーケンスを含まない文字列として出力する．
+/// 各行は`\n`で区切られ，各フレームの末尾にも`\n`が書き込まれる．
+#[derive(Debug)]
+pub struct PlainTextBackend<W: io::Write> {
+    /// 出力先．
+    writer: W,
+    /// 描画中のフレームの内容．
+    buffer: String,
+    /// 描画中のフレームにおいて，最後に描画した点の行．
+    current_row: Option<CanvasLattice>,
+}
+
+/// 描画内容を`RenderedCanvas`としてメモリ上に保持する．
+/// 描画結果の検査や，他の出力形式への変換に用いる．
+/// 枠を含めたフレーム全体を保持するため，各点のレイヤーは持たない．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryBackend {
+    /// 最後に描画したフレームの内容．
+    frame: RenderedCanvas<()>,
+    /// 描画を終了したフレームの数．
+    frame_count: usize,
+}
+
+impl<W: io::Write> AnsiBackend<W> {
+    /// 指定した出力先に，`ColorSupport::current`で表現可能な色で描画内容を出力するバックエンドを返す．
+    pub fn new(writer: W) -> Self {
+        Self::with_color_support(writer, ColorSupport::current())
+    }
+
+    /// 指定した出力先に，指定した色表現範囲で描画内容を出力するバックエンドを返す．
+    pub fn with_color_support(writer: W, color_support: ColorSupport) -> Self {
+        Self {
+            writer,
+            writes_to_terminal: false,
+            color_support,
+            buffer: String::new(),
+            current_style: UnitStyle::plain(),
+            cursor: None,
+        }
+    }
+
+    /// このオブジェクトを破棄し，出力先を返す．
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl AnsiBackend<io::Stdout> {
+    /// 標準出力に，`ColorSupport::current`で表現可能な色で描画内容を出力するバックエンドを返す．
+    /// 表示可能な領域のサイズは，標準出力が接続された端末のサイズとなる．
+    pub fn stdout() -> Self {
+        Self {
+            writes_to_terminal: true,
+            ..Self::new(io::stdout())
+        }
+    }
+}
+
+impl<W: io::Write> Backend for AnsiBackend<W> {
+    fn begin_frame(&mut self, _size: Pair<CanvasLattice>) -> Result<(), DrawError> {
+        self.buffer.clear();
+        self.current_style = UnitStyle::plain();
+        self.cursor = None;
+        Ok(())
+    }
+
+    fn draw_cell(
+        &mut self,
+        position: CanvasItemPosition,
+        unit: DrawableUnit,
+    ) -> Result<(), DrawError> {
+        use std::fmt::Write;
+        if self.cursor != Some(position) {
+            // 描画単位は端末上の2列を占有する
+            let column: TerminalLattice = position.x * 2;
+            write!(self.buffer, "\x1b[{};{}H", position.y + 1, column + 1)?;
+        }
+        let style = unit.style().downgrade(self.color_support);
+        if style != self.current_style {
+            if style.is_plain() {
+                UnitStyle::write_reset_to(&mut self.buffer)?;
+            } else {
+                style.write_sgr_to(&mut self.buffer)?;
+            }
+            self.current_style = style;
+        }
+        unit.write_glyphs_to(&mut self.buffer)?;
+        self.cursor = Some(CanvasItemPosition::new(position.x + 1, position.y));
+        Ok(())
+    }
+
+    fn end_frame(&mut self) -> Result<(), DrawError> {
+        if !self.current_style.is_plain() {
+            UnitStyle::write_reset_to(&mut self.buffer)?;
+            self.current_style = UnitStyle::plain();
+        }
+        self.writer.write_all(self.buffer.as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn size(&self) -> Option<Pair<CanvasLattice>> {
+        if self.writes_to_terminal {
+            terminal::terminal_size().map(|size| Pair::new(size.x / 2, size.y))
+        } else {
+            None
+        }
+    }
+}
+
+impl<W: io::Write> PlainTextBackend<W> {
+    /// 指定した出力先に描画内容を出力するバックエンドを返す．
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: String::new(),
+            current_row: None,
+        }
+    }
+
+    /// このオブジェクトを破棄し，出力先を返す．
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: io::Write> Backend for PlainTextBackend<W> {
+    fn begin_frame(&mut self, _size: Pair<CanvasLattice>) -> Result<(), DrawError> {
+        self.buffer.clear();
+        self.current_row = None;
+        Ok(())
+    }
+
+    fn draw_cell(
+        &mut self,
+        position: CanvasItemPosition,
+        unit: DrawableUnit,
+    ) -> Result<(), DrawError> {
+        if let Some(row) = self.current_row {
+            for _ in row..position.y {
+                self.buffer.push('\n');
+            }
+        }
+        self.current_row = Some(position.y);
+        unit.write_glyphs_to(&mut self.buffer)?;
+        Ok(())
+    }
+
+    fn end_frame(&mut self) -> Result<(), DrawError> {
+        if self.current_row.is_some() {
+            self.buffer.push('\n');
+        }
+        self.writer.write_all(self.buffer.as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn size(&self) -> Option<Pair<CanvasLattice>> {
+        None
+    }
+}
+
+impl MemoryBackend {
+    /// 何も描画されていない状態のバックエンドを返す．
+    pub fn new() -> Self {
+        Self {
+            frame: RenderedCanvas::blank(Pair::new(0, 0)),
+            frame_count: 0,
+        }
+    }
+
+    /// 最後に描画したフレームの内容を返す．
+    pub fn frame(&self) -> &RenderedCanvas<()> {
+        &self.frame
+    }
+
+    /// 最後に描画したフレームのサイズを返す．
+    pub fn frame_size(&self) -> Pair<CanvasLattice> {
+        self.frame.size()
+    }
+
+    /// 描画を終了したフレームの数を返す．
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    /// 最後に描画したフレームの，指定した点の描画単位を返す．
+    /// 描画されていない点，およびフレーム外の点については`None`を返す．
+    pub fn unit_at(&self, position: CanvasItemPosition) -> Option<DrawableUnit> {
+        self.frame.cell(position).map(|(unit, _)| unit)
+    }
+}
+
+impl Default for MemoryBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn begin_frame(&mut self, size: Pair<CanvasLattice>) -> Result<(), DrawError> {
+        self.frame = RenderedCanvas::blank(size);
+        Ok(())
+    }
+
+    fn draw_cell(
+        &mut self,
+        position: CanvasItemPosition,
+        unit: DrawableUnit,
+    ) -> Result<(), DrawError> {
+        if self.frame.set_cell(position, (unit, ())) {
+            Ok(())
+        } else {
+            Err(DrawError::OutOfBounds {
+                position,
+                size: self.frame.size(),
+            })
+        }
+    }
+
+    fn end_frame(&mut self) -> Result<(), DrawError> {
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    /// 最後に描画したフレームのサイズを返す．まだフレームを描画していない場合は`None`を返す．
+    fn size(&self) -> Option<Pair<CanvasLattice>> {
+        if self.frame_count > 0 {
+            Some(self.frame.size())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BorderStyle, Canvas, UnitColor};
+
+    fn sample_canvas() -> Canvas<i32> {
+        let mut canvas = Canvas::with_size(Pair::new(2, 2));
+        canvas.set_border_style(BorderStyle::Ascii(UnitColor::White));
+        canvas.draw_unit(
+            DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red),
+            CanvasItemPosition::new(1, 0),
+            0,
+        );
+        canvas
+    }
+
+    #[test]
+    fn memory_backend() {
+        let canvas = sample_canvas();
+        let mut backend = MemoryBackend::new();
+        assert_eq!(None, backend.size());
+        canvas.render_to(&mut backend).unwrap();
+        assert_eq!(Some(Pair::new(4, 4)), backend.size());
+        assert_eq!(Pair::new(4, 4), backend.frame_size());
+        assert_eq!(1, backend.frame_count());
+        assert_eq!(
+            canvas.unit_at(CanvasItemPosition::new(1, 0)),
+            backend.unit_at(CanvasItemPosition::new(2, 1))
+        );
+        assert_eq!(
+            BorderStyle::Ascii(UnitColor::White)
+                .units()
+                .map(|units| units.top_left),
+            backend.unit_at(CanvasItemPosition::new(0, 0))
+        );
+        assert_eq!(None, backend.unit_at(CanvasItemPosition::new(4, 0)));
+        assert_eq!(
+            " +----+ \n |  ab| \n |    | \n +----+ ",
+            backend.frame().to_string()
+        );
+    }
+
+    #[test]
+    fn plain_text_backend() {
+        let canvas = sample_canvas();
+        let mut backend = PlainTextBackend::new(Vec::new());
+        canvas.render_to(&mut backend).unwrap();
+        let output = String::from_utf8(backend.into_inner()).unwrap();
+        assert_eq!(" +----+ \n |  ab| \n |    | \n +----+ \n", output);
+    }
+
+    #[test]
+    fn ansi_backend() {
+        let mut canvas = Canvas::with_size(Pair::new(2, 1));
+        canvas.set_border_style(BorderStyle::None);
+        canvas.draw_unit(
+            DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red),
+            CanvasItemPosition::new(1, 0),
+            0,
+        );
+        let mut backend = AnsiBackend::with_color_support(Vec::new(), ColorSupport::Ansi16);
+        // 標準出力以外の出力先では，端末のサイズを表示可能な領域とみなさない
+        assert_eq!(None, backend.size());
+        canvas.render_to(&mut backend).unwrap();
+        let output = String::from_utf8(backend.into_inner()).unwrap();
+        assert_eq!("\x1b[1;1H\x1b[0;37m  \x1b[0;31mab\x1b[0m", output);
+    }
+}
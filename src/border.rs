@@ -0,0 +1,117 @@
+use crate::{CanvasLattice, DrawableUnit, UnitColor};
+
+/// キャンバスの枠を構成する描画単位の組．
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorderUnits {
+    pub top: DrawableUnit,
+    pub bottom: DrawableUnit,
+    pub left: DrawableUnit,
+    pub right: DrawableUnit,
+    pub top_left: DrawableUnit,
+    pub top_right: DrawableUnit,
+    pub bottom_left: DrawableUnit,
+    pub bottom_right: DrawableUnit,
+}
+
+/// キャンバスの枠の描画方法を表す．
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorderStyle {
+    /// 枠を描画しない．
+    None,
+    /// 上辺を`_`，左右を`|`，下辺を`￣`で描画する．
+    Classic(UnitColor),
+    /// 辺を`-`と`|`，角を`+`で描画する．
+    Ascii(UnitColor),
+    /// 罫線素片 (一重線)で描画する．
+    Single(UnitColor),
+    /// 罫線素片 (二重線)で描画する．
+    Double(UnitColor),
+    /// 各辺および各角に，指定した描画単位を用いる．
+    Custom(BorderUnits),
+}
+
+impl BorderUnits {
+    /// 各角と各辺に描画する文字を指定して，枠を構成する描画単位の組を返す．
+    /// 左右の辺と角は，キャンバスの内容に隣接する側に文字が寄せられる．
+    /// # Params
+    /// 1. `horizontal` 上下の辺に描画する文字．
+    /// 1. `vertical` 左右の辺に描画する文字．
+    /// 1. `corners` 左上，右上，左下，右下の角にそれぞれ描画する文字．
+    fn from_half_chars(
+        horizontal: char,
+        vertical: char,
+        corners: [char; 4],
+        color: UnitColor,
+    ) -> Self {
+        let [top_left, top_right, bottom_left, bottom_right] = corners;
+        Self {
+            top: DrawableUnit::from_double_half_char(horizontal, horizontal, color),
+            bottom: DrawableUnit::from_double_half_char(horizontal, horizontal, color),
+            left: DrawableUnit::from_double_half_char(' ', vertical, color),
+            right: DrawableUnit::from_double_half_char(vertical, ' ', color),
+            top_left: DrawableUnit::from_double_half_char(' ', top_left, color),
+            top_right: DrawableUnit::from_double_half_char(top_right, ' ', color),
+            bottom_left: DrawableUnit::from_double_half_char(' ', bottom_left, color),
+            bottom_right: DrawableUnit::from_double_half_char(bottom_right, ' ', color),
+        }
+    }
+}
+
+impl BorderStyle {
+    /// この枠を構成する描画単位の組を返す．
+    /// 枠を描画しない場合は`None`を返す．
+    pub fn units(&self) -> Option<BorderUnits> {
+        match *self {
+            BorderStyle::None => None,
+            BorderStyle::Classic(color) => {
+                let top = DrawableUnit::from_double_half_char('_', '_', color);
+                let bottom = DrawableUnit::from_single_full_char('￣', color);
+                let left = DrawableUnit::from_double_half_char(' ', '|', color);
+                let right = DrawableUnit::from_double_half_char('|', ' ', color);
+                Some(BorderUnits {
+                    top,
+                    bottom,
+                    left,
+                    right,
+                    top_left: top,
+                    top_right: top,
+                    bottom_left: bottom,
+                    bottom_right: bottom,
+                })
+            }
+            BorderStyle::Ascii(color) => Some(BorderUnits::from_half_chars(
+                '-',
+                '|',
+                ['+', '+', '+', '+'],
+                color,
+            )),
+            BorderStyle::Single(color) => Some(BorderUnits::from_half_chars(
+                '─',
+                '│',
+                ['┌', '┐', '└', '┘'],
+                color,
+            )),
+            BorderStyle::Double(color) => Some(BorderUnits::from_half_chars(
+                '═',
+                '║',
+                ['╔', '╗', '╚', '╝'],
+                color,
+            )),
+            BorderStyle::Custom(units) => Some(units),
+        }
+    }
+
+    /// この枠の厚さを，描画単位を単位として返す．
+    pub fn thickness(&self) -> CanvasLattice {
+        match self {
+            BorderStyle::None => 0,
+            _ => 1,
+        }
+    }
+}
+
+impl Default for BorderStyle {
+    fn default() -> Self {
+        BorderStyle::Classic(UnitColor::White)
+    }
+}
@@ -1,8 +1,14 @@
-use crate::{DrawDestination, DrawError, DrawableUnit, Layer, UnitColor};
+use crate::{
+    terminal, Backend, BorderStyle, ColorSupport, DrawDestination, DrawError, DrawableUnit, Layer,
+    TerminalLattice, UnitColor, UnitStyle,
+};
 use data_structure::Pair;
+use geometry::Rectangle;
 
-const CANVAS_WIDTH: CanvasLattice = (40 - 2);
-const CANVAS_HEIGHT: CanvasLattice = 30;
+/// `Canvas::empty_canvas`で生成されるキャンバスの幅．
+const DEFAULT_CANVAS_WIDTH: CanvasLattice = 40 - 2;
+/// `Canvas::empty_canvas`で生成されるキャンバスの高さ．
+const DEFAULT_CANVAS_HEIGHT: CanvasLattice = 30;
 
 /// キャンバス内の描画先座標の成分となる型．
 pub type CanvasLattice = usize;
@@ -17,16 +23,31 @@ struct CanvasUnit<L> {
     layer: L,
 }
 
+/// キャンバスのサイズの決め方を表す．
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeMode {
+    /// キャンバスのサイズは，明示的に変更しない限り一定である．
+    Fixed,
+    /// キャンバスのサイズは，端末のサイズに追従する．
+    FollowTerminal,
+}
+
 /// ストリームにゲーム情報を描画する．
 pub struct Canvas<L> {
-    /// 固定長の2次元キャンバスの各点の情報．
-    lattices: [[Option<CanvasUnit<L>>; CANVAS_WIDTH]; CANVAS_HEIGHT],
+    /// キャンバスのサイズ．
+    size: Pair<CanvasLattice>,
+    /// キャンバスのサイズの決め方．
+    size_mode: SizeMode,
+    /// キャンバスの枠の描画方法．
+    border_style: BorderStyle,
+    /// 2次元キャンバスの各点の情報．行優先で格納される．
+    lattices: Vec<Option<CanvasUnit<L>>>,
 }
 
 impl<L> Canvas<L> {
     /// このキャンバスを描画した場合のサイズ (コンソール上の最小の正方形に対するサイズ)を返す．
     pub const fn size(&self) -> Pair<CanvasLattice> {
-        Pair::new(CANVAS_WIDTH, CANVAS_HEIGHT)
+        self.size
     }
 
     /// 指定した点がこのキャンバスの領域内にあり，描画可能であるか返す．
@@ -34,27 +55,302 @@ impl<L> Canvas<L> {
         let size = self.size();
         (position.x < size.x) & (position.y < size.y)
     }
+
+    /// このキャンバスのサイズの決め方を返す．
+    pub const fn size_mode(&self) -> SizeMode {
+        self.size_mode
+    }
+
+    /// このキャンバスの枠の描画方法を返す．
+    pub const fn border_style(&self) -> BorderStyle {
+        self.border_style
+    }
+
+    /// キャンバスの枠の描画方法を変更する．
+    pub fn set_border_style(&mut self, border_style: BorderStyle) {
+        self.border_style = border_style;
+    }
+
+    /// 枠を含めたこのキャンバスのサイズを返す．
+    pub(crate) fn framed_size(&self) -> Pair<CanvasLattice> {
+        let thickness = self.border_style.thickness();
+        Pair::new(self.size.x + thickness * 2, self.size.y + thickness * 2)
+    }
+
+    /// 枠を含めたキャンバス上の指定した点に表示される描画単位を返す．
+    /// # Params
+    /// 1. `framed_position` 枠の左上の角を`(0, 0)`とした座標．
+    pub(crate) fn framed_unit_at(&self, framed_position: CanvasItemPosition) -> DrawableUnit {
+        let units = match self.border_style.units() {
+            Some(units) => units,
+            None => return self.displayed_unit_at(framed_position),
+        };
+        let framed_size = self.framed_size();
+        let is_top = framed_position.y == 0;
+        let is_bottom = framed_position.y + 1 == framed_size.y;
+        let is_left = framed_position.x == 0;
+        let is_right = framed_position.x + 1 == framed_size.x;
+        match (is_top, is_bottom, is_left, is_right) {
+            (true, _, true, _) => units.top_left,
+            (true, _, _, true) => units.top_right,
+            (_, true, true, _) => units.bottom_left,
+            (_, true, _, true) => units.bottom_right,
+            (true, _, _, _) => units.top,
+            (_, true, _, _) => units.bottom,
+            (_, _, true, _) => units.left,
+            (_, _, _, true) => units.right,
+            _ => self.displayed_unit_at(framed_position - Pair::new(1, 1)),
+        }
+    }
+
+    /// 指定した点に描画されている描画単位を返す．
+    /// 何も描画されていない点，およびキャンバス外の点については`None`を返す．
+    pub fn unit_at(&self, position: CanvasItemPosition) -> Option<DrawableUnit> {
+        self.lattice_at(position).map(|l| l.drawable_unit)
+    }
+
+    /// 指定した点の情報を返す．キャンバス外の点については`None`を返す．
+    fn lattice_at(&self, position: CanvasItemPosition) -> Option<&CanvasUnit<L>> {
+        if self.is_drawable_at(position) {
+            self.lattices[self.index_of(position)].as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// 指定した点の情報が`lattices`内のどこに格納されているか返す．
+    fn index_of(&self, position: CanvasItemPosition) -> usize {
+        position.y * self.size.x + position.x
+    }
+
+    /// 指定した点に表示される描画単位を返す．
+    /// 何も描画されていない点については，空白を表す描画単位を返す．
+    pub(crate) fn displayed_unit_at(&self, position: CanvasItemPosition) -> DrawableUnit {
+        self.lattice_at(position)
+            .map(|l| l.drawable_unit)
+            .unwrap_or_else(Self::empty_drawable_unit)
+    }
+
+    /// `write_to`で書き込まれる文字列上における，キャンバス内の点`(0, 0)`の位置を文字セル単位で返す．
+    pub(crate) fn content_origin(&self) -> Pair<TerminalLattice> {
+        let thickness = self.border_style.thickness();
+        Pair::new(thickness * 2, thickness)
+    }
+
+    fn empty_drawable_unit() -> DrawableUnit {
+        DrawableUnit::from_double_half_char(' ', ' ', UnitColor::White)
+    }
 }
 
 impl<L: Layer> Canvas<L> {
-    /// すべての点を空白にした状態のキャンバスを返す．
+    /// すべての点を空白にした状態のキャンバスを，既定のサイズで返す．
     pub fn empty_canvas() -> Self {
+        Self::with_size(Pair::new(DEFAULT_CANVAS_WIDTH, DEFAULT_CANVAS_HEIGHT))
+    }
+
+    /// 指定した数の点を保持するのに必要なバイト数を返す．
+    /// 確保できる大きさを超える場合は`None`を返す．
+    pub(crate) fn storage_bytes(cell_count: usize) -> Option<usize> {
+        cell_count
+            .checked_mul(std::mem::size_of::<Option<CanvasUnit<L>>>())
+            .filter(|&bytes| bytes <= isize::MAX as usize)
+    }
+
+    /// すべての点を空白にした状態のキャンバスを，指定したサイズで返す．
+    /// # Params
+    /// 1. `size` キャンバスのサイズ (コンソール上の最小の正方形に対するサイズ)．
+    pub fn with_size(size: Pair<CanvasLattice>) -> Self {
+        Self {
+            size,
+            size_mode: SizeMode::Fixed,
+            border_style: BorderStyle::default(),
+            lattices: vec![None; size.x * size.y],
+        }
+    }
+
+    /// すべての点を空白にした状態で，現在の端末に枠を含めて収まるサイズのキャンバスを返す．
+    /// 返されるキャンバスは`SizeMode::FollowTerminal`となり，`sync_terminal_size`によって端末のサイズに追従する．
+    ///
+    /// 端末のサイズを取得できない場合は，既定のサイズのキャンバスを返す．
+    pub fn fit_to_terminal() -> Self {
+        let border_thickness = BorderStyle::default().thickness();
+        let size = terminal::terminal_size()
+            .map(|terminal_size| {
+                terminal::canvas_size_for_terminal(terminal_size, border_thickness)
+            })
+            .unwrap_or_else(|| Pair::new(DEFAULT_CANVAS_WIDTH, DEFAULT_CANVAS_HEIGHT));
         Self {
-            lattices: [[None; CANVAS_WIDTH]; CANVAS_HEIGHT],
+            size_mode: SizeMode::FollowTerminal,
+            ..Self::with_size(size)
+        }
+    }
+
+    /// キャンバスのサイズを変更する．
+    /// サイズが変わった場合，キャンバスの内容はすべてクリアされる．
+    pub fn resize(&mut self, size: Pair<CanvasLattice>) {
+        if self.size != size {
+            self.size = size;
+            self.lattices = vec![None; size.x * size.y];
         }
     }
 
+    /// キャンバスのサイズの決め方を変更する．
+    pub fn set_size_mode(&mut self, size_mode: SizeMode) {
+        self.size_mode = size_mode;
+    }
+
+    /// このキャンバスが`SizeMode::FollowTerminal`である場合，現在の端末のサイズに合わせてキャンバスのサイズを変更する．
+    /// 毎フレームの描画前に呼び出すことを想定している．
+    /// # Returns
+    /// キャンバスのサイズが変更された (したがって内容がクリアされた)場合は`true`を返す．
+    pub fn sync_terminal_size(&mut self) -> bool {
+        if self.size_mode != SizeMode::FollowTerminal {
+            return false;
+        }
+        match terminal::terminal_size() {
+            Some(terminal_size) => {
+                let previous_size = self.size;
+                let border_thickness = self.border_style.thickness();
+                self.resize(terminal::canvas_size_for_terminal(
+                    terminal_size,
+                    border_thickness,
+                ));
+                previous_size != self.size
+            }
+            None => false,
+        }
+    }
+
+    /// 指定した点に表示されている描画単位のレイヤーを返す．
+    /// 何も描画されていない点，およびキャンバス外の点については`None`を返す．
+    pub fn layer_at(&self, position: CanvasItemPosition) -> Option<L> {
+        self.lattice_at(position).map(|l| l.layer)
+    }
+
+    /// このキャンバスに描画されているすべての点について，その位置，描画単位およびレイヤーを行優先で列挙する．
+    /// 何も描画されていない点は列挙されない．
+    pub fn units(&self) -> impl Iterator<Item = (CanvasItemPosition, DrawableUnit, L)> + '_ {
+        let width = self.size.x;
+        self.lattices
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, lattice)| {
+                lattice.map(|l| {
+                    let position = CanvasItemPosition::new(index % width, index / width);
+                    (position, l.drawable_unit, l.layer)
+                })
+            })
+    }
+
     /// オブジェクトを指定した位置およびレイヤーに描画する．
     /// 指定した点に，より上位のレイヤーで描画されているオブジェクトが存在する場合，描画内容は更新されない．
+    /// 指定した点がキャンバス外にある場合は何も描画しない．
+    /// 描画できなかったことを知る必要がある場合は`try_draw_unit`を用いる．
     pub fn draw_unit(
         &mut self,
         drawable_unit: DrawableUnit,
         position: CanvasItemPosition,
         layer: L,
     ) {
-        debug_assert!(position.x < CANVAS_WIDTH);
-        debug_assert!(position.y < CANVAS_HEIGHT);
-        let lattice = &mut self.lattices[position.y][position.x];
+        self.put_unit(drawable_unit, position, layer);
+    }
+
+    /// オブジェクトを指定した位置およびレイヤーに描画する．
+    /// 指定した点に，より上位のレイヤーで描画されているオブジェクトが存在する場合，描画内容は更新されない．
+    /// # Returns
+    /// 以下の場合は，キャンバスを変更せずにエラーを返す．
+    /// 1. 指定した点がキャンバス外にある場合．
+    /// 1. 描画単位がコンソール上の最小の正方形領域に収まらない場合．
+    pub fn try_draw_unit(
+        &mut self,
+        drawable_unit: DrawableUnit,
+        position: CanvasItemPosition,
+        layer: L,
+    ) -> Result<(), DrawError> {
+        if !self.is_drawable_at(position) {
+            return Err(DrawError::OutOfBounds {
+                position,
+                size: self.size,
+            });
+        }
+        if !drawable_unit.has_valid_width() {
+            return Err(DrawError::InvalidGlyphWidth(drawable_unit));
+        }
+        self.put_unit(drawable_unit, position, layer);
+        Ok(())
+    }
+
+    /// 別のキャンバス`source`の矩形領域`source_region` (両端の点を含む)の内容を，その左上の点が`destination`となるように描画する．
+    /// `source`の各点のレイヤーは`layer_mapping`によってこのキャンバスのレイヤーに変換され，
+    /// 各点の描画内容は`draw_unit`と同じくレイヤーの優先度に従って更新される．
+    /// `source`で何も描画されていない点，`source`外の点，およびこのキャンバス外にはみ出す点は描画されない．
+    /// # Examples
+    /// ```rust
+    /// use cui_gaming::{Canvas, CanvasItemPosition, DrawableUnit, UnitColor};
+    /// use data_structure::Pair;
+    /// use geometry::Rectangle;
+    ///
+    /// let mut minimap = Canvas::with_size(Pair::new(2, 2));
+    /// let unit = DrawableUnit::from_double_half_char('[', ']', UnitColor::White);
+    /// minimap.draw_unit(unit, CanvasItemPosition::new(1, 1), 0);
+    ///
+    /// let mut screen = Canvas::with_size(Pair::new(5, 5));
+    /// let region = Rectangle::from_corners(Pair::new(0, 0), Pair::new(1, 1));
+    /// screen.blit(&minimap, region, CanvasItemPosition::new(3, 3), |layer| layer + 10);
+    /// assert_eq!(Some(unit), screen.unit_at(CanvasItemPosition::new(4, 4)));
+    /// assert_eq!(Some(10), screen.layer_at(CanvasItemPosition::new(4, 4)));
+    /// ```
+    pub fn blit<M, F>(
+        &mut self,
+        source: &Canvas<M>,
+        source_region: Rectangle<CanvasLattice>,
+        destination: CanvasItemPosition,
+        mut layer_mapping: F,
+    ) where
+        M: Layer,
+        F: FnMut(M) -> L,
+    {
+        let source_size = source.size();
+        if source_size.x == 0 || source_size.y == 0 {
+            return;
+        }
+        // 描画元の領域を，描画元のキャンバス内に切り詰める
+        let right = source_region.right().min(source_size.x - 1);
+        let bottom = source_region.bottom().min(source_size.y - 1);
+        for y in source_region.top()..=bottom {
+            for x in source_region.left()..=right {
+                let source_position = CanvasItemPosition::new(x, y);
+                let lattice = match source.lattice_at(source_position) {
+                    Some(lattice) => *lattice,
+                    None => continue,
+                };
+                // 座標が桁あふれする点もキャンバス外として扱う
+                let position = match (
+                    destination.x.checked_add(x - source_region.left()),
+                    destination.y.checked_add(y - source_region.top()),
+                ) {
+                    (Some(x), Some(y)) => CanvasItemPosition::new(x, y),
+                    _ => continue,
+                };
+                if self.is_drawable_at(position) {
+                    self.put_unit(
+                        lattice.drawable_unit,
+                        position,
+                        layer_mapping(lattice.layer),
+                    );
+                }
+            }
+        }
+    }
+
+    /// レイヤーの優先度に従い，指定した点の描画内容を更新する．
+    /// キャンバス外の点は，他の点の内容を書き換えないよう無視する．
+    fn put_unit(&mut self, drawable_unit: DrawableUnit, position: CanvasItemPosition, layer: L) {
+        if !self.is_drawable_at(position) {
+            return;
+        }
+        let index = self.index_of(position);
+        let lattice = &mut self.lattices[index];
         match lattice {
             Some(l) if layer >= l.layer => {
                 *lattice = Some(CanvasUnit {
@@ -72,51 +368,386 @@ impl<L: Layer> Canvas<L> {
         }
     }
 
-    /// このキャンバスの内容をすべて文字列として書き込む．
+    /// このキャンバスの内容を，枠とともにすべて文字列として書き込む．
+    /// 色は`ColorSupport::current`で表現可能な範囲に変換される．
     pub fn write_to<D: DrawDestination>(&self, destination: &mut D) -> Result<(), DrawError> {
-        const CANVAS_BOUNDARY_COLOR: UnitColor = UnitColor::White;
-        // top boundary
-        for _ in 0..CANVAS_WIDTH + 2 {
-            DrawableUnit::from_double_half_char('_', '_', CANVAS_BOUNDARY_COLOR)
-                .write_to(destination)?;
-        }
-        destination.write_char('\n')?;
-        //
-        for lattice_row in self.lattices.iter() {
-            // left boundary
-            DrawableUnit::from_double_half_char(' ', '|', CANVAS_BOUNDARY_COLOR)
-                .write_to(destination)?;
-            // canvas contents
-            for lattice in lattice_row.iter() {
-                lattice
-                    .map(|l| l.drawable_unit)
-                    .unwrap_or(Self::empty_drawable_unit())
-                    .write_to(destination)?;
+        self.write_with_color_support_to(destination, ColorSupport::current())
+    }
+
+    /// このキャンバスの内容を，色を指定した色表現範囲に変換したうえで，枠とともにすべて文字列として書き込む．
+    /// 同じスタイルの描画単位が連続する場合，スタイルを指定するエスケープシーケンスはその先頭でのみ書き込まれる．
+    pub fn write_with_color_support_to<D: DrawDestination>(
+        &self,
+        destination: &mut D,
+        color_support: ColorSupport,
+    ) -> Result<(), DrawError> {
+        let framed_size = self.framed_size();
+        for y in 0..framed_size.y {
+            if y > 0 {
+                destination.write_char('\n')?;
+            }
+            // 各行は既定のスタイルで始まり，既定のスタイルで終わる
+            let mut current_style = UnitStyle::plain();
+            for x in 0..framed_size.x {
+                let unit = self.framed_unit_at(CanvasItemPosition::new(x, y));
+                let style = unit.style().downgrade(color_support);
+                if style != current_style {
+                    if style.is_plain() {
+                        UnitStyle::write_reset_to(destination)?;
+                    } else {
+                        style.write_sgr_to(destination)?;
+                    }
+                    current_style = style;
+                }
+                unit.write_glyphs_to(destination)?;
+            }
+            if !current_style.is_plain() {
+                UnitStyle::write_reset_to(destination)?;
             }
-            // right boundary
-            DrawableUnit::from_double_half_char('|', ' ', CANVAS_BOUNDARY_COLOR)
-                .write_to(destination)?;
-            //
-            destination.write_char('\n')?;
         }
-        // bottom boundary
-        for _ in 0..CANVAS_WIDTH + 2 {
-            DrawableUnit::from_single_full_char('￣', CANVAS_BOUNDARY_COLOR)
-                .write_to(destination)?;
+        Ok(())
+    }
+
+    /// このキャンバスの内容を，エスケープシーケンスを含まない文字列として書き込む．
+    /// 各行は`\n`で区切られ，最終行の後には改行は書き込まれない．
+    /// # Params
+    /// 1. `with_border` 枠も書き込むか．`false`の場合，枠の描画方法によらず枠は書き込まれない．
+    pub fn write_plain_to<D: DrawDestination>(
+        &self,
+        destination: &mut D,
+        with_border: bool,
+    ) -> Result<(), DrawError> {
+        let (size, offset) = if with_border {
+            (self.framed_size(), Pair::new(0, 0))
+        } else {
+            let thickness = self.border_style.thickness();
+            (self.size, Pair::new(thickness, thickness))
+        };
+        for y in 0..size.y {
+            if y > 0 {
+                destination.write_char('\n')?;
+            }
+            for x in 0..size.x {
+                self.framed_unit_at(CanvasItemPosition::new(x, y) + offset)
+                    .write_glyphs_to(destination)?;
+            }
         }
         Ok(())
     }
 
+    /// このキャンバスの内容を，エスケープシーケンスを含まない文字列として返す．
+    /// 書式は`write_plain_to`と同じである．
+    pub fn to_plain_string(&self, with_border: bool) -> String {
+        let mut s = String::new();
+        self.write_plain_to(&mut s, with_border)
+            .expect("Writing to String never fails.");
+        s
+    }
+
+    /// このキャンバスの内容を，枠とともに指定したバックエンドに描画する．
+    pub fn render_to<B: Backend>(&self, backend: &mut B) -> Result<(), DrawError> {
+        let framed_size = self.framed_size();
+        backend.begin_frame(framed_size)?;
+        for y in 0..framed_size.y {
+            for x in 0..framed_size.x {
+                let position = CanvasItemPosition::new(x, y);
+                backend.draw_cell(position, self.framed_unit_at(position))?;
+            }
+        }
+        backend.end_frame()
+    }
+
     /// このキャンバス全体をクリアする．
     pub fn clear(&mut self) {
-        for lattice_row in self.lattices.iter_mut() {
-            for lattice in lattice_row.iter_mut() {
-                *lattice = None;
+        for lattice in self.lattices.iter_mut() {
+            *lattice = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UnitAttributes;
+    #[test]
+    fn test_with_size() {
+        let canvas = Canvas::<i32>::with_size(Pair::new(5, 3));
+        assert_eq!(Pair::new(5, 3), canvas.size());
+        assert!(canvas.is_drawable_at(CanvasItemPosition::new(4, 2)));
+        assert!(!canvas.is_drawable_at(CanvasItemPosition::new(5, 2)));
+        assert!(!canvas.is_drawable_at(CanvasItemPosition::new(4, 3)));
+    }
+    #[test]
+    fn test_resize() {
+        let mut canvas = Canvas::with_size(Pair::new(2, 2));
+        let unit = DrawableUnit::from_double_half_char('a', 'b', UnitColor::White);
+        canvas.draw_unit(unit, CanvasItemPosition::new(1, 1), 0);
+        canvas.resize(Pair::new(4, 1));
+        assert_eq!(Pair::new(4, 1), canvas.size());
+        assert!(canvas.is_drawable_at(CanvasItemPosition::new(3, 0)));
+        assert!(!canvas.is_drawable_at(CanvasItemPosition::new(1, 1)));
+        // 固定サイズのキャンバスは端末に追従しない
+        assert!(!canvas.sync_terminal_size());
+        assert_eq!(Pair::new(4, 1), canvas.size());
+    }
+    #[test]
+    fn test_write_to_honors_size() {
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        let unit = DrawableUnit::from_double_half_char('a', 'b', UnitColor::White);
+        canvas.draw_unit(unit, CanvasItemPosition::new(2, 1), 0);
+        let mut s = String::new();
+        canvas
+            .write_with_color_support_to(&mut s, ColorSupport::TrueColor)
+            .unwrap();
+        let lines = console::strip_ansi_codes(&s)
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        assert_eq!(4, lines.len());
+        assert_eq!("__________", lines[0]);
+        assert_eq!(" |      | ", lines[1]);
+        assert_eq!(" |    ab| ", lines[2]);
+        assert_eq!("￣￣￣￣￣", lines[3]);
+    }
+    #[test]
+    fn test_draw_unit_out_of_bounds() {
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        let unit = DrawableUnit::from_double_half_char('a', 'b', UnitColor::White);
+        // 最終行以外の右側にはみ出した点は，次の行の点として扱われない
+        canvas.draw_unit(unit, CanvasItemPosition::new(5, 0), 0);
+        canvas.draw_unit(unit, CanvasItemPosition::new(3, 0), 0);
+        canvas.draw_unit(unit, CanvasItemPosition::new(0, 2), 0);
+        let mut s = String::new();
+        canvas
+            .write_with_color_support_to(&mut s, ColorSupport::TrueColor)
+            .unwrap();
+        let lines = console::strip_ansi_codes(&s)
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        assert_eq!(" |      | ", lines[1]);
+        assert_eq!(" |      | ", lines[2]);
+    }
+    #[test]
+    fn test_read_back() {
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        let a = DrawableUnit::from_double_half_char('a', 'a', UnitColor::White);
+        let b = DrawableUnit::from_double_half_char('b', 'b', UnitColor::Red);
+        canvas.draw_unit(a, CanvasItemPosition::new(2, 0), 1);
+        canvas.draw_unit(b, CanvasItemPosition::new(1, 1), 2);
+        // 下位のレイヤーへの描画は反映されない
+        canvas.draw_unit(a, CanvasItemPosition::new(1, 1), 0);
+        assert_eq!(Some(a), canvas.unit_at(CanvasItemPosition::new(2, 0)));
+        assert_eq!(Some(1), canvas.layer_at(CanvasItemPosition::new(2, 0)));
+        assert_eq!(Some(b), canvas.unit_at(CanvasItemPosition::new(1, 1)));
+        assert_eq!(Some(2), canvas.layer_at(CanvasItemPosition::new(1, 1)));
+        assert_eq!(None, canvas.unit_at(CanvasItemPosition::new(0, 0)));
+        assert_eq!(None, canvas.layer_at(CanvasItemPosition::new(0, 0)));
+        assert_eq!(None, canvas.unit_at(CanvasItemPosition::new(3, 0)));
+        assert_eq!(
+            vec![
+                (CanvasItemPosition::new(2, 0), a, 1),
+                (CanvasItemPosition::new(1, 1), b, 2)
+            ],
+            canvas.units().collect::<Vec<_>>()
+        );
+    }
+    #[test]
+    fn test_try_draw_unit() {
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        let unit = DrawableUnit::from_double_half_char('a', 'a', UnitColor::White);
+        assert!(canvas
+            .try_draw_unit(unit, CanvasItemPosition::new(2, 1), 0)
+            .is_ok());
+        assert_eq!(Some(unit), canvas.unit_at(CanvasItemPosition::new(2, 1)));
+        match canvas.try_draw_unit(unit, CanvasItemPosition::new(3, 1), 0) {
+            Err(DrawError::OutOfBounds { position, size }) => {
+                assert_eq!(CanvasItemPosition::new(3, 1), position);
+                assert_eq!(Pair::new(3, 2), size);
             }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        // 正方形に収まらない描画単位は描画されない
+        let invalid = DrawableUnit::from_chars_unchecked('a', Some('\t'));
+        match canvas.try_draw_unit(invalid, CanvasItemPosition::new(0, 0), 0) {
+            Err(DrawError::InvalidGlyphWidth(unit)) => assert_eq!(invalid, unit),
+            other => panic!("unexpected result: {:?}", other),
         }
+        assert_eq!(None, canvas.unit_at(CanvasItemPosition::new(0, 0)));
+        // キャンバス外の点については，幅よりも先に位置が検査される
+        assert!(matches!(
+            canvas.try_draw_unit(invalid, CanvasItemPosition::new(0, 2), 0),
+            Err(DrawError::OutOfBounds { .. })
+        ));
     }
+    #[test]
+    fn test_write_batches_style_runs() {
+        let mut canvas = Canvas::with_size(Pair::new(4, 1));
+        canvas.set_border_style(BorderStyle::None);
+        let red = DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red);
+        let blue = DrawableUnit::from_double_half_char('c', 'd', UnitColor::Blue);
+        canvas.draw_unit(red, CanvasItemPosition::new(0, 0), 0);
+        canvas.draw_unit(red, CanvasItemPosition::new(1, 0), 0);
+        canvas.draw_unit(blue, CanvasItemPosition::new(2, 0), 0);
+        let mut s = String::new();
+        canvas
+            .write_with_color_support_to(&mut s, ColorSupport::Ansi16)
+            .unwrap();
+        assert_eq!("\x1b[0;31mabab\x1b[0;34mcd\x1b[0;37m  \x1b[0m", s);
+        let mut s = String::new();
+        canvas
+            .write_with_color_support_to(&mut s, ColorSupport::Monochrome)
+            .unwrap();
+        assert_eq!("ababcd  ", s);
+    }
+    #[test]
+    fn test_background_change_starts_new_run() {
+        let mut canvas = Canvas::with_size(Pair::new(4, 1));
+        canvas.set_border_style(BorderStyle::None);
+        let on_blue = DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red)
+            .with_background(UnitColor::Blue);
+        let on_green = on_blue.with_background(UnitColor::Green);
+        let plain = DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red);
+        canvas.draw_unit(on_blue, CanvasItemPosition::new(0, 0), 0);
+        canvas.draw_unit(on_blue, CanvasItemPosition::new(1, 0), 0);
+        canvas.draw_unit(on_green, CanvasItemPosition::new(2, 0), 0);
+        canvas.draw_unit(plain, CanvasItemPosition::new(3, 0), 0);
+        let mut s = String::new();
+        canvas
+            .write_with_color_support_to(&mut s, ColorSupport::Ansi16)
+            .unwrap();
+        // 文字色が同じでも，背景色が変わると新たな連続部分となる
+        assert_eq!("\x1b[0;31;44mabab\x1b[0;31;42mab\x1b[0;31mab\x1b[0m", s);
+    }
+    #[test]
+    fn test_write_resets_style_at_line_end() {
+        let mut canvas = Canvas::<i32>::with_size(Pair::new(1, 2));
+        canvas.set_border_style(BorderStyle::None);
+        let mut s = String::new();
+        canvas
+            .write_with_color_support_to(&mut s, ColorSupport::Ansi16)
+            .unwrap();
+        assert_eq!("\x1b[0;37m  \x1b[0m\n\x1b[0;37m  \x1b[0m", s);
+    }
+    #[test]
+    fn test_to_plain_string() {
+        let mut canvas = Canvas::with_size(Pair::new(2, 2));
+        canvas.set_border_style(BorderStyle::Single(UnitColor::Red));
+        let unit = DrawableUnit::from_single_full_char('あ', UnitColor::Green)
+            .with_attributes(UnitAttributes::BOLD);
+        canvas.draw_unit(unit, CanvasItemPosition::new(1, 1), 0);
+        assert_eq!("    \n  あ", canvas.to_plain_string(false));
+        assert_eq!(
+            " ┌────┐ \n │    │ \n │  あ│ \n └────┘ ",
+            canvas.to_plain_string(true)
+        );
+        canvas.set_border_style(BorderStyle::None);
+        assert_eq!("    \n  あ", canvas.to_plain_string(true));
+        assert_eq!("    \n  あ", canvas.to_plain_string(false));
+    }
+    fn plain_lines<L: Layer>(canvas: &Canvas<L>) -> Vec<String> {
+        let mut s = String::new();
+        canvas
+            .write_with_color_support_to(&mut s, ColorSupport::TrueColor)
+            .unwrap();
+        console::strip_ansi_codes(&s)
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+    #[test]
+    fn test_blit() {
+        let mut source = Canvas::with_size(Pair::new(3, 2));
+        let a = DrawableUnit::from_double_half_char('a', 'a', UnitColor::White);
+        let b = DrawableUnit::from_double_half_char('b', 'b', UnitColor::Red);
+        let c = DrawableUnit::from_single_full_char('あ', UnitColor::Blue);
+        source.draw_unit(a, CanvasItemPosition::new(0, 0), 1);
+        source.draw_unit(b, CanvasItemPosition::new(1, 0), 1);
+        source.draw_unit(c, CanvasItemPosition::new(2, 1), 3);
 
-    fn empty_drawable_unit() -> DrawableUnit {
-        DrawableUnit::from_double_half_char(' ', ' ', UnitColor::White)
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        let high = DrawableUnit::from_double_half_char('#', '#', UnitColor::White);
+        canvas.draw_unit(high, CanvasItemPosition::new(2, 0), 5);
+        // 領域は描画されていない点およびキャンバス外の点を含んでよい
+        canvas.blit(
+            &source,
+            Rectangle::from_corners(Pair::new(0, 0), Pair::new(2, 1)),
+            CanvasItemPosition::new(1, 0),
+            |layer: i32| layer * 2,
+        );
+        assert_eq!(None, canvas.unit_at(CanvasItemPosition::new(0, 0)));
+        assert_eq!(Some(a), canvas.unit_at(CanvasItemPosition::new(1, 0)));
+        assert_eq!(Some(2), canvas.layer_at(CanvasItemPosition::new(1, 0)));
+        // より上位のレイヤーで描画された点は更新されない
+        assert_eq!(Some(high), canvas.unit_at(CanvasItemPosition::new(2, 0)));
+        // 描画されていない点は，描画先の内容を消さない
+        assert_eq!(None, canvas.unit_at(CanvasItemPosition::new(1, 1)));
+        // はみ出した点は，描画先の他の点に回り込んで描画されない
+        for y in 0..2 {
+            assert_eq!(None, canvas.unit_at(CanvasItemPosition::new(0, y)));
+        }
+        for x in 0..3 {
+            assert_eq!(None, canvas.unit_at(CanvasItemPosition::new(x, 1)));
+        }
+        assert_eq!(2, canvas.units().count());
+
+        // 描画元の領域外の点は描画されない
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        canvas.blit(
+            &source,
+            Rectangle::from_corners(Pair::new(1, 0), Pair::new(2, 1)),
+            CanvasItemPosition::new(0, 0),
+            |layer: i32| layer,
+        );
+        assert_eq!(Some(b), canvas.unit_at(CanvasItemPosition::new(0, 0)));
+        assert_eq!(Some(c), canvas.unit_at(CanvasItemPosition::new(1, 1)));
+        assert_eq!(Some(3), canvas.layer_at(CanvasItemPosition::new(1, 1)));
+        assert_eq!(2, canvas.units().count());
+
+        // 描画元を大きく超える領域や，桁あふれする描画先の位置も扱える
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        let whole = Rectangle::from_corners(Pair::new(0, 0), Pair::new(usize::MAX, usize::MAX));
+        canvas.blit(
+            &source,
+            whole,
+            CanvasItemPosition::new(0, 0),
+            |layer: i32| layer,
+        );
+        assert_eq!(3, canvas.units().count());
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        canvas.blit(
+            &source,
+            whole,
+            CanvasItemPosition::new(usize::MAX, usize::MAX),
+            |layer: i32| layer,
+        );
+        assert_eq!(0, canvas.units().count());
+    }
+    #[test]
+    fn test_border_style() {
+        let mut canvas = Canvas::with_size(Pair::new(2, 1));
+        let unit = DrawableUnit::from_double_half_char('a', 'b', UnitColor::White);
+        canvas.draw_unit(unit, CanvasItemPosition::new(0, 0), 0);
+        canvas.set_border_style(BorderStyle::Ascii(UnitColor::White));
+        assert_eq!(
+            vec![" +----+ ", " |ab  | ", " +----+ "],
+            plain_lines(&canvas)
+        );
+        canvas.set_border_style(BorderStyle::Double(UnitColor::Blue));
+        assert_eq!(
+            vec![" ╔════╗ ", " ║ab  ║ ", " ╚════╝ "],
+            plain_lines(&canvas)
+        );
+        canvas.set_border_style(BorderStyle::None);
+        assert_eq!(vec!["ab  "], plain_lines(&canvas));
+        assert_eq!(Pair::new(0, 0), canvas.content_origin());
+    }
+    #[test]
+    fn test_custom_border_style() {
+        let mut canvas = Canvas::<i32>::with_size(Pair::new(1, 1));
+        let mut units = BorderStyle::Single(UnitColor::White).units().unwrap();
+        units.top_left = DrawableUnit::from_single_full_char('＊', UnitColor::Red);
+        canvas.set_border_style(BorderStyle::Custom(units));
+        assert_eq!(vec!["＊──┐ ", " │  │ ", " └──┘ "], plain_lines(&canvas));
     }
 }
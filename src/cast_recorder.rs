@@ -0,0 +1,139 @@
+use crate::TerminalLattice;
+use data_structure::Pair;
+use std::io;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// asciicast v2形式のバージョン番号．
+const ASCIICAST_VERSION: u32 = 2;
+
+/// 端末に出力したフレームを，asciinemaで再生可能なasciicast v2形式(`.cast`)で記録する．
+///
+/// 生成時にヘッダ行を書き込み，以降は`record_frame`が呼ばれるたびに，
+/// 記録開始からの経過時間とフレームの出力内容を1つの出力イベントとして書き込む．
+/// `FrameWriter`と組み合わせる場合は，フレームの書き込み後に`FrameWriter::last_frame`を記録すればよい．
+/// `TerminalSession::start_recording`を用いると，表示したフレームが自動的に記録される．
+#[derive(Debug)]
+pub struct CastRecorder<W: io::Write> {
+    /// 記録の出力先．
+    writer: W,
+    /// 記録を開始した時刻．
+    start: Instant,
+}
+
+impl<W: io::Write> CastRecorder<W> {
+    /// 指定した出力先にヘッダ行を書き込み，記録を開始する．
+    /// `terminal_size`は記録する端末の列数を`x`，行数を`y`とした組である．
+    pub fn new(mut writer: W, terminal_size: Pair<TerminalLattice>) -> io::Result<Self> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        writeln!(
+            writer,
+            "{{\"version\": {}, \"width\": {}, \"height\": {}, \"timestamp\": {}}}",
+            ASCIICAST_VERSION, terminal_size.x, terminal_size.y, timestamp
+        )?;
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    /// 端末へ出力した1フレーム分の内容を，記録開始からの経過時間とともに書き込む．
+    /// 再生時の端末は出力を変換しないため，`\r`を伴わない改行は`\r\n`として記録される．
+    pub fn record_frame(&mut self, output: &str) -> io::Result<()> {
+        let elapsed = self.start.elapsed();
+        self.write_event(elapsed, output)
+    }
+
+    /// 出力先への参照を返す．
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// このオブジェクトを破棄し，出力先を返す．
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// 経過時間`elapsed`における出力イベントを1行書き込み，フラッシュする．
+    fn write_event(&mut self, elapsed: Duration, output: &str) -> io::Result<()> {
+        let mut line = format!("[{:.6}, \"o\", \"", elapsed.as_secs_f64());
+        let mut previous = None;
+        for c in output.chars() {
+            match c {
+                '"' => line.push_str("\\\""),
+                '\\' => line.push_str("\\\\"),
+                // 端末の改行変換(ONLCR)を再現する
+                '\n' if previous == Some('\r') => line.push_str("\\n"),
+                '\n' => line.push_str("\\r\\n"),
+                '\r' => line.push_str("\\r"),
+                '\t' => line.push_str("\\t"),
+                c if (c as u32) < 0x20 || c == '\u{7f}' => {
+                    line.push_str(&format!("\\u{:04x}", c as u32))
+                }
+                c => line.push(c),
+            }
+            previous = Some(c);
+        }
+        line.push_str("\"]\n");
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Canvas, ColorSupport};
+
+    #[test]
+    fn test_header() {
+        let recorder = CastRecorder::new(Vec::new(), Pair::new(80, 24)).unwrap();
+        let cast = String::from_utf8(recorder.into_inner()).unwrap();
+        assert!(cast.starts_with("{\"version\": 2, \"width\": 80, \"height\": 24, \"timestamp\": "));
+        assert!(cast.ends_with("}\n"));
+        assert_eq!(1, cast.lines().count());
+    }
+
+    #[test]
+    fn test_write_event() {
+        let mut recorder = CastRecorder::new(Vec::new(), Pair::new(80, 24)).unwrap();
+        recorder
+            .write_event(Duration::from_millis(1500), "\x1b[0;31ma\"\\\nあ")
+            .unwrap();
+        let cast = String::from_utf8(recorder.into_inner()).unwrap();
+        assert_eq!(
+            Some("[1.500000, \"o\", \"\\u001b[0;31ma\\\"\\\\\\r\\nあ\"]"),
+            cast.lines().nth(1)
+        );
+    }
+
+    #[test]
+    fn one_event_per_frame() {
+        let mut recorder = CastRecorder::new(Vec::new(), Pair::new(80, 24)).unwrap();
+        recorder.record_frame("a").unwrap();
+        recorder.record_frame("b").unwrap();
+        let cast = String::from_utf8(recorder.into_inner()).unwrap();
+        assert_eq!(3, cast.lines().count());
+        assert!(cast.lines().nth(2).unwrap().ends_with(", \"o\", \"b\"]"));
+    }
+
+    #[test]
+    fn canvas_line_breaks_are_recorded_as_crlf() {
+        let mut frame = String::new();
+        Canvas::<i32>::with_size(Pair::new(3, 2))
+            .write_with_color_support_to(&mut frame, ColorSupport::TrueColor)
+            .unwrap();
+        assert!(frame.lines().count() > 1);
+        let mut recorder = CastRecorder::new(Vec::new(), Pair::new(80, 24)).unwrap();
+        recorder.record_frame(&frame).unwrap();
+        let cast = String::from_utf8(recorder.into_inner()).unwrap();
+        let event = cast.lines().nth(1).unwrap();
+        assert_eq!(frame.matches('\n').count(), event.matches("\\r\\n").count());
+        assert_eq!(
+            event.matches("\\n").count(),
+            event.matches("\\r\\n").count()
+        );
+    }
+}
@@ -0,0 +1,451 @@
+extern crate console;
+
+use crate::DrawDestination;
+use std::env;
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 端末上で表現可能な色の範囲を表す．
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorSupport {
+    /// 色を表現できない．このとき，文字色および背景色は指定されず，文字装飾のみが書き込まれる．
+    Monochrome,
+    /// ANSI 16色．
+    Ansi16,
+    /// xterm 256色パレット．
+    Ansi256,
+    /// 24bit RGB色．
+    TrueColor,
+}
+
+/// 描画単位の文字色および背景色を表す．
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnitColor {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+    /// xterm 256色パレット中の色．
+    Ansi256(u8),
+    /// 24bit RGB色．
+    Rgb(u8, u8, u8),
+}
+
+/// 端末に色を指定する際の表現方法．
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorCode {
+    /// ANSI 16色の番号 (0から15)．
+    Ansi16(u8),
+    /// xterm 256色パレットの番号．
+    Ansi256(u8),
+    /// 24bit RGB値．
+    Rgb(u8, u8, u8),
+}
+
+/// `ColorSupport::current`が検出した色表現範囲．0は未検出を，その他の値は`ColorSupport`の値に1を加えたものを表す．
+static CURRENT_COLOR_SUPPORT: AtomicU8 = AtomicU8::new(0);
+
+/// 色表現範囲を明示的に指定するための環境変数名．
+/// 値には`monochrome`，`16`，`256`，`truecolor`のいずれかを指定できる．
+pub const COLOR_SUPPORT_ENVIRONMENT_VARIABLE: &str = "CUI_GAMING_COLOR";
+
+/// ANSI 16色を，xtermにおける既定のRGB値で表したもの．
+const ANSI16_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// xterm 256色パレットの色立方体 (16番から231番)の各成分がとる値．
+const COLOR_CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorSupport {
+    /// 現在の端末の色表現範囲を返す．
+    /// 初回の呼び出し時に`detect`で検出した値を，以後の呼び出しでも返す．
+    ///
+    /// この値は，色表現範囲を指定せずに生成した`FrameWriter`，`AnsiBackend`，`DiffRenderer`および`TerminalSession`の既定値となる．
+    /// 特定の色表現範囲で出力する場合は，それぞれの`with_color_support`などで生成時に指定する．
+    pub fn current() -> Self {
+        match Self::from_u8(CURRENT_COLOR_SUPPORT.load(Ordering::Relaxed)) {
+            Some(support) => support,
+            None => {
+                let support = Self::detect();
+                CURRENT_COLOR_SUPPORT.store(support as u8 + 1, Ordering::Relaxed);
+                support
+            }
+        }
+    }
+
+    /// 環境変数および標準出力の状態から，端末の色表現範囲を検出する．
+    /// 以下の順に判定する．
+    /// 1. 環境変数`CUI_GAMING_COLOR`が有効な値をとる場合は，その値．
+    /// 1. 環境変数`NO_COLOR`が空でない場合，または標準出力が端末でない場合は`Monochrome`．
+    /// 1. 環境変数`COLORTERM`が`truecolor`または`24bit`の場合は`TrueColor`．
+    /// 1. 環境変数`TERM`が`dumb`の場合は`Monochrome`，`256color`を含む場合は`Ansi256`．
+    /// 1. その他の場合は`Ansi16`．
+    pub fn detect() -> Self {
+        let variable = |name| env::var(name).ok();
+        Self::from_environment(
+            variable(COLOR_SUPPORT_ENVIRONMENT_VARIABLE).as_deref(),
+            variable("NO_COLOR").as_deref(),
+            variable("COLORTERM").as_deref(),
+            variable("TERM").as_deref(),
+            console::user_attended(),
+        )
+    }
+
+    /// 環境変数の値および標準出力が端末であるかどうかから，色表現範囲を決定する．
+    fn from_environment(
+        explicit: Option<&str>,
+        no_color: Option<&str>,
+        color_term: Option<&str>,
+        term: Option<&str>,
+        is_terminal: bool,
+    ) -> Self {
+        if let Some(support) = explicit.and_then(Self::parse) {
+            return support;
+        }
+        if matches!(no_color, Some(v) if !v.is_empty()) || !is_terminal {
+            return ColorSupport::Monochrome;
+        }
+        if let Some("truecolor") | Some("24bit") = color_term {
+            return ColorSupport::TrueColor;
+        }
+        match term {
+            Some("dumb") => ColorSupport::Monochrome,
+            Some(term) if term.contains("256color") => ColorSupport::Ansi256,
+            _ => ColorSupport::Ansi16,
+        }
+    }
+
+    /// `CUI_GAMING_COLOR`に指定された値を解釈する．
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "monochrome" | "none" => Some(ColorSupport::Monochrome),
+            "16" => Some(ColorSupport::Ansi16),
+            "256" => Some(ColorSupport::Ansi256),
+            "truecolor" | "24bit" => Some(ColorSupport::TrueColor),
+            _ => None,
+        }
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(ColorSupport::Monochrome),
+            2 => Some(ColorSupport::Ansi16),
+            3 => Some(ColorSupport::Ansi256),
+            4 => Some(ColorSupport::TrueColor),
+            _ => None,
+        }
+    }
+}
+
+impl UnitColor {
+    /// 指定した色表現範囲で表現できる，この色にもっとも近い色を返す．
+    /// `ColorSupport::Monochrome`の場合は`None`を返す．
+    pub fn downgrade(self, support: ColorSupport) -> Option<Self> {
+        match (support, self) {
+            (ColorSupport::Monochrome, _) => None,
+            (ColorSupport::TrueColor, color) => Some(color),
+            (ColorSupport::Ansi256, UnitColor::Rgb(r, g, b)) => {
+                Some(UnitColor::Ansi256(nearest_ansi256_index((r, g, b))))
+            }
+            (ColorSupport::Ansi256, color) => Some(color),
+            (ColorSupport::Ansi16, UnitColor::Ansi256(index)) if index < 16 => {
+                Some(Self::from_ansi16_index(index))
+            }
+            (ColorSupport::Ansi16, color) => match color.ansi16_index() {
+                Some(_) => Some(color),
+                None => Some(Self::from_ansi16_index(nearest_ansi16_index(color.rgb()))),
+            },
+        }
+    }
+
+    /// この色をRGB値で表したものを返す．ANSI 16色はxtermの既定値で近似される．
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        match self.code() {
+            ColorCode::Ansi16(index) => ANSI16_PALETTE[index as usize],
+            ColorCode::Ansi256(index) => ansi256_rgb(index),
+            ColorCode::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+
+    /// ANSI 16色の番号 (0から15)に対応する色を返す．
+    pub(crate) fn from_ansi16_index(index: u8) -> Self {
+        const COLORS: [UnitColor; 16] = [
+            UnitColor::Black,
+            UnitColor::Red,
+            UnitColor::Green,
+            UnitColor::Yellow,
+            UnitColor::Blue,
+            UnitColor::Magenta,
+            UnitColor::Cyan,
+            UnitColor::White,
+            UnitColor::BrightBlack,
+            UnitColor::BrightRed,
+            UnitColor::BrightGreen,
+            UnitColor::BrightYellow,
+            UnitColor::BrightBlue,
+            UnitColor::BrightMagenta,
+            UnitColor::BrightCyan,
+            UnitColor::BrightWhite,
+        ];
+        COLORS[index as usize]
+    }
+
+    /// この色を文字色として指定するSGRパラメータを，`;`を前置して書き込む．
+    pub(crate) fn write_foreground_parameters<D: DrawDestination>(
+        &self,
+        destination: &mut D,
+    ) -> fmt::Result {
+        self.write_parameters(30, 90, 38, destination)
+    }
+
+    /// この色を背景色として指定するSGRパラメータを，`;`を前置して書き込む．
+    pub(crate) fn write_background_parameters<D: DrawDestination>(
+        &self,
+        destination: &mut D,
+    ) -> fmt::Result {
+        self.write_parameters(40, 100, 48, destination)
+    }
+
+    /// この色を指定するSGRパラメータを，`;`を前置して書き込む．
+    /// # Params
+    /// 1. `base` 基本8色の0番目の色に対応するパラメータ．
+    /// 1. `bright_base` 高輝度8色の0番目の色に対応するパラメータ．
+    /// 1. `extended` 256色およびRGB色の指定に用いるパラメータ．
+    fn write_parameters<D: DrawDestination>(
+        &self,
+        base: u8,
+        bright_base: u8,
+        extended: u8,
+        destination: &mut D,
+    ) -> fmt::Result {
+        match self.code() {
+            ColorCode::Ansi16(index) if index < 8 => write!(destination, ";{}", base + index),
+            ColorCode::Ansi16(index) => write!(destination, ";{}", bright_base + index - 8),
+            ColorCode::Ansi256(index) => write!(destination, ";{};5;{}", extended, index),
+            ColorCode::Rgb(r, g, b) => write!(destination, ";{};2;{};{};{}", extended, r, g, b),
+        }
+    }
+
+    /// この色がANSI 16色のいずれかである場合，その番号 (0から15)を返す．
+    pub(crate) fn ansi16_index(&self) -> Option<u8> {
+        match self.code() {
+            ColorCode::Ansi16(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    /// この色を端末に指定する際の表現方法を返す．
+    fn code(&self) -> ColorCode {
+        let index = match *self {
+            UnitColor::Black => 0,
+            UnitColor::Red => 1,
+            UnitColor::Green => 2,
+            UnitColor::Yellow => 3,
+            UnitColor::Blue => 4,
+            UnitColor::Magenta => 5,
+            UnitColor::Cyan => 6,
+            UnitColor::White => 7,
+            UnitColor::BrightBlack => 8,
+            UnitColor::BrightRed => 9,
+            UnitColor::BrightGreen => 10,
+            UnitColor::BrightYellow => 11,
+            UnitColor::BrightBlue => 12,
+            UnitColor::BrightMagenta => 13,
+            UnitColor::BrightCyan => 14,
+            UnitColor::BrightWhite => 15,
+            UnitColor::Ansi256(index) => return ColorCode::Ansi256(index),
+            UnitColor::Rgb(r, g, b) => return ColorCode::Rgb(r, g, b),
+        };
+        ColorCode::Ansi16(index)
+    }
+}
+
+/// xterm 256色パレット中の指定した番号の色をRGB値で返す．
+fn ansi256_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI16_PALETTE[index as usize],
+        16..=231 => {
+            let index = index - 16;
+            (
+                COLOR_CUBE_LEVELS[(index / 36) as usize],
+                COLOR_CUBE_LEVELS[(index / 6 % 6) as usize],
+                COLOR_CUBE_LEVELS[(index % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+/// 2つのRGB値の距離の2乗を返す．
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// 指定したRGB値にもっとも近いANSI 16色の番号を返す．
+fn nearest_ansi16_index(rgb: (u8, u8, u8)) -> u8 {
+    (0..16u8)
+        .min_by_key(|&index| distance(rgb, ANSI16_PALETTE[index as usize]))
+        .unwrap()
+}
+
+/// 指定したRGB値にもっとも近い，xterm 256色パレット中の色の番号を返す．
+/// 色立方体およびグレースケールの中から選ばれる．
+fn nearest_ansi256_index(rgb: (u8, u8, u8)) -> u8 {
+    let nearest_level = |component: u8| {
+        (0..COLOR_CUBE_LEVELS.len())
+            .min_by_key(|&i| (i32::from(COLOR_CUBE_LEVELS[i]) - i32::from(component)).abs())
+            .unwrap() as u8
+    };
+    let cube_index =
+        16 + 36 * nearest_level(rgb.0) + 6 * nearest_level(rgb.1) + nearest_level(rgb.2);
+    let gray_index = {
+        let average = (u32::from(rgb.0) + u32::from(rgb.1) + u32::from(rgb.2)) / 3;
+        232 + (average.saturating_sub(3) / 10).min(23) as u8
+    };
+    if distance(rgb, ansi256_rgb(gray_index)) < distance(rgb, ansi256_rgb(cube_index)) {
+        gray_index
+    } else {
+        cube_index
+    }
+}
+
+impl From<console::Color> for UnitColor {
+    fn from(color: console::Color) -> Self {
+        match color {
+            console::Color::Black => UnitColor::Black,
+            console::Color::Red => UnitColor::Red,
+            console::Color::Green => UnitColor::Green,
+            console::Color::Yellow => UnitColor::Yellow,
+            console::Color::Blue => UnitColor::Blue,
+            console::Color::Magenta => UnitColor::Magenta,
+            console::Color::Cyan => UnitColor::Cyan,
+            console::Color::White => UnitColor::White,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn foreground(color: UnitColor) -> String {
+        let mut s = String::new();
+        color.write_foreground_parameters(&mut s).unwrap();
+        s
+    }
+    fn background(color: UnitColor) -> String {
+        let mut s = String::new();
+        color.write_background_parameters(&mut s).unwrap();
+        s
+    }
+    #[test]
+    fn test_parameters() {
+        assert_eq!(";30", foreground(UnitColor::Black));
+        assert_eq!(";37", foreground(UnitColor::White));
+        assert_eq!(";91", foreground(UnitColor::BrightRed));
+        assert_eq!(";38;5;208", foreground(UnitColor::Ansi256(208)));
+        assert_eq!(";38;2;1;2;3", foreground(UnitColor::Rgb(1, 2, 3)));
+        assert_eq!(";44", background(UnitColor::Blue));
+        assert_eq!(";107", background(UnitColor::BrightWhite));
+        assert_eq!(";48;5;0", background(UnitColor::Ansi256(0)));
+        assert_eq!(";48;2;255;128;0", background(UnitColor::Rgb(255, 128, 0)));
+    }
+    #[test]
+    fn test_downgrade() {
+        let orange = UnitColor::Rgb(255, 135, 0);
+        assert_eq!(Some(orange), orange.downgrade(ColorSupport::TrueColor));
+        assert_eq!(
+            Some(UnitColor::Ansi256(208)),
+            orange.downgrade(ColorSupport::Ansi256)
+        );
+        assert_eq!(
+            Some(UnitColor::Ansi256(244)),
+            UnitColor::Rgb(128, 128, 130).downgrade(ColorSupport::Ansi256)
+        );
+        assert_eq!(
+            Some(UnitColor::BrightYellow),
+            UnitColor::Ansi256(226).downgrade(ColorSupport::Ansi16)
+        );
+        assert_eq!(
+            Some(UnitColor::BrightRed),
+            UnitColor::Ansi256(9).downgrade(ColorSupport::Ansi16)
+        );
+        assert_eq!(
+            Some(UnitColor::Blue),
+            UnitColor::Blue.downgrade(ColorSupport::Ansi16)
+        );
+        assert_eq!(None, UnitColor::Blue.downgrade(ColorSupport::Monochrome));
+    }
+    #[test]
+    fn test_ansi256_rgb() {
+        assert_eq!((0, 0, 0), UnitColor::Ansi256(16).rgb());
+        assert_eq!((255, 135, 0), UnitColor::Ansi256(208).rgb());
+        assert_eq!((238, 238, 238), UnitColor::Ansi256(255).rgb());
+        assert_eq!((205, 0, 0), UnitColor::Red.rgb());
+    }
+    #[test]
+    fn test_from_environment() {
+        use ColorSupport::*;
+        let detect = |explicit, no_color, color_term, term, is_terminal| {
+            ColorSupport::from_environment(explicit, no_color, color_term, term, is_terminal)
+        };
+        assert_eq!(Ansi16, detect(None, None, None, Some("xterm"), true));
+        assert_eq!(
+            Ansi256,
+            detect(None, None, None, Some("xterm-256color"), true)
+        );
+        assert_eq!(
+            TrueColor,
+            detect(None, None, Some("truecolor"), Some("xterm-256color"), true)
+        );
+        assert_eq!(Monochrome, detect(None, None, None, Some("dumb"), true));
+        assert_eq!(
+            Monochrome,
+            detect(None, Some("1"), Some("24bit"), None, true)
+        );
+        assert_eq!(Ansi16, detect(None, Some(""), None, None, true));
+        // 標準出力が端末でない場合は色を用いない
+        assert_eq!(
+            Monochrome,
+            detect(None, None, Some("truecolor"), None, false)
+        );
+        // 明示的な指定はすべてに優先する
+        assert_eq!(Ansi256, detect(Some("256"), Some("1"), None, None, false));
+        assert_eq!(
+            Monochrome,
+            detect(Some("none"), None, Some("truecolor"), None, true)
+        );
+        assert_eq!(Ansi16, detect(Some("invalid"), None, None, None, true));
+    }
+}
@@ -1,15 +1,32 @@
-extern crate console;
 extern crate unicode_width;
 
+use crate::{
+    CanvasItemPosition, CanvasLattice, ColorSupport, UnitAttributes, UnitColor, UnitStyle,
+};
+use data_structure::Pair;
 use std::fmt;
-
-pub type UnitColor = console::Color;
+use std::io;
 
 /// 描画先となれる型であることを表す．
 pub trait DrawDestination: fmt::Write {}
 
 /// 描画時のエラーを表す型．
-pub type DrawError = fmt::Error;
+#[derive(Debug)]
+pub enum DrawError {
+    /// 描画先の点がキャンバス外にある．
+    OutOfBounds {
+        /// 描画先として指定された点．
+        position: CanvasItemPosition,
+        /// 描画先キャンバスのサイズ．
+        size: Pair<CanvasLattice>,
+    },
+    /// 描画単位が，コンソール上の最小の正方形領域に収まらない文字を含んでいる．
+    InvalidGlyphWidth(DrawableUnit),
+    /// 描画先への書き込みに失敗した．
+    Destination(fmt::Error),
+    /// 描画内容の出力先 (`io::Write`)への書き込みに失敗した．
+    Io(io::Error),
+}
 
 /// 描画する内容の最小単位を表す．
 /// このオブジェクトは，コンソール上の最小の正方形領域内に描画されることが保証されている．
@@ -18,11 +35,54 @@ pub struct DrawableUnit {
     left: char,
     right: Option<char>,
     color: UnitColor,
+    /// 背景色．`None`の場合は端末の既定の背景色で描画される．
+    background: Option<UnitColor>,
+    /// 文字装飾．
+    attributes: UnitAttributes,
 }
 
 // Auto trait implementation
 impl<W: fmt::Write> DrawDestination for W {}
 
+impl fmt::Display for DrawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrawError::OutOfBounds { position, size } => write!(
+                f,
+                "position ({}, {}) is out of the canvas of size ({}, {})",
+                position.x, position.y, size.x, size.y
+            ),
+            DrawError::InvalidGlyphWidth(unit) => {
+                write!(f, "unit {:?} does not fit in a square on console", unit)
+            }
+            DrawError::Destination(e) => write!(f, "failed to write to destination: {}", e),
+            DrawError::Io(e) => write!(f, "failed to write to output: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DrawError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DrawError::Destination(e) => Some(e),
+            DrawError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<fmt::Error> for DrawError {
+    fn from(e: fmt::Error) -> Self {
+        DrawError::Destination(e)
+    }
+}
+
+impl From<io::Error> for DrawError {
+    fn from(e: io::Error) -> Self {
+        DrawError::Io(e)
+    }
+}
+
 impl DrawableUnit {
     /// 描画時の占有領域がコンソール上の最小の正方形となるような描画単位を返す．
     /// # Panics on Debug Build
@@ -34,6 +94,8 @@ impl DrawableUnit {
             left: c,
             right: None,
             color,
+            background: None,
+            attributes: UnitAttributes::NONE,
         }
     }
 
@@ -48,6 +110,8 @@ impl DrawableUnit {
             left,
             right: Some(right),
             color,
+            background: None,
+            attributes: UnitAttributes::NONE,
         }
     }
 
@@ -97,55 +161,122 @@ impl DrawableUnit {
         units
     }
 
+    /// このオブジェクトの文字色を指定した色に変更したものを返す．
+    pub(crate) fn with_color(self, color: UnitColor) -> Self {
+        Self { color, ..self }
+    }
+
+    /// このオブジェクトの背景色を指定した色に変更したものを返す．
+    pub fn with_background(self, background: UnitColor) -> Self {
+        Self {
+            background: Some(background),
+            ..self
+        }
+    }
+
+    /// このオブジェクトの文字装飾を指定したものに変更したものを返す．
+    pub fn with_attributes(self, attributes: UnitAttributes) -> Self {
+        Self { attributes, ..self }
+    }
+
+    /// このオブジェクトが表示する文字を，左から順に列挙する．
+    pub fn chars(&self) -> impl Iterator<Item = char> {
+        std::iter::once(self.left).chain(self.right)
+    }
+
+    /// このオブジェクトの文字色を返す．
+    pub fn color(&self) -> UnitColor {
+        self.color
+    }
+
+    /// このオブジェクトの背景色を返す．背景色が指定されていない場合は`None`を返す．
+    pub fn background(&self) -> Option<UnitColor> {
+        self.background
+    }
+
+    /// このオブジェクトの文字装飾を返す．
+    pub fn attributes(&self) -> UnitAttributes {
+        self.attributes
+    }
+
+    /// このオブジェクトの文字色，背景色および文字装飾をまとめて返す．
+    pub fn style(&self) -> UnitStyle {
+        UnitStyle {
+            foreground: Some(self.color),
+            background: self.background,
+            attributes: self.attributes,
+        }
+    }
+
+    /// このオブジェクトが，コンソール上の最小の正方形領域内に収まるか返す．
+    /// Releaseビルドでは生成時にチェックが行われないため，描画前の検証に用いる．
+    pub fn has_valid_width(&self) -> bool {
+        use unicode_width::UnicodeWidthChar;
+        match self.right {
+            Some(right) => self.left.width() == Some(1) && right.width() == Some(1),
+            None => self.left.width() == Some(2),
+        }
+    }
+
     /// このオブジェクトを指定した描画先に書き込む．
     /// このオブジェクトは，コンソール上の最小の正方形領域内に描画されることが保証されている．
+    ///
+    /// 色は`ColorSupport::current`で表現可能な範囲に変換される．
     pub fn write_to<D: DrawDestination>(&self, destination: &mut D) -> Result<(), DrawError> {
-        use std::iter::FromIterator;
-        let colored_str = {
-            let s = match self.right {
-                Some(right) => String::from_iter(&[self.left, right]),
-                None => self.left.to_string(),
-            };
-            let temp_style = console::style(s);
-            match self.color {
-                UnitColor::Black => temp_style.black(),
-                UnitColor::Blue => temp_style.blue(),
-                UnitColor::Cyan => temp_style.cyan(),
-                UnitColor::Green => temp_style.green(),
-                UnitColor::Magenta => temp_style.magenta(),
-                UnitColor::Red => temp_style.red(),
-                UnitColor::White => temp_style.white(),
-                UnitColor::Yellow => temp_style.yellow(),
-            }
-        };
-        destination.write_fmt(format_args!("{}", colored_str))?;
+        self.write_with_color_support_to(destination, ColorSupport::current())
+    }
+
+    /// このオブジェクトを，色を指定した色表現範囲に変換したうえで描画先に書き込む．
+    /// 変換されるのは色のみであり，文字装飾はいずれの色表現範囲でも書き込まれる．
+    pub fn write_with_color_support_to<D: DrawDestination>(
+        &self,
+        destination: &mut D,
+        color_support: ColorSupport,
+    ) -> Result<(), DrawError> {
+        let style = self.style().downgrade(color_support);
+        // SGRシーケンスで文字色，背景色および装飾を指定し，書き込み後にリセットする
+        if !style.is_plain() {
+            style.write_sgr_to(destination)?;
+        }
+        self.write_glyphs_to(destination)?;
+        if !style.is_plain() {
+            UnitStyle::write_reset_to(destination)?;
+        }
+        Ok(())
+    }
+
+    /// このオブジェクトの文字のみを，スタイルを指定せずに書き込む．
+    pub(crate) fn write_glyphs_to<D: DrawDestination>(&self, destination: &mut D) -> fmt::Result {
+        destination.write_char(self.left)?;
+        if let Some(right) = self.right {
+            destination.write_char(right)?;
+        }
         Ok(())
     }
 }
 
 #[cfg(test)]
-mod test_util {
-    use super::*;
-    pub fn get_string_without_style(units: &[DrawableUnit]) -> String {
-        let mut s = String::new();
-        for unit in units {
-            s.push(unit.left);
-            if let Some(right) = unit.right {
-                s.push(right);
-            }
+impl DrawableUnit {
+    /// 文字の幅を検査せずに描画単位を返す．不正な描画単位に対する処理の検査に用いる．
+    pub(crate) fn from_chars_unchecked(left: char, right: Option<char>) -> Self {
+        Self {
+            left,
+            right,
+            color: UnitColor::White,
+            background: None,
+            attributes: UnitAttributes::NONE,
         }
-        s
     }
 }
 
 #[cfg(test)]
 mod tests_from_single_char {
     use super::*;
-    use super::test_util::get_string_without_style;
+    use crate::testing::string_without_style;
     #[test]
     fn pass() {
         let unit = DrawableUnit::from_single_full_char('あ', UnitColor::White);
-        assert_eq!("あ", get_string_without_style(&[unit]));
+        assert_eq!("あ", string_without_style(&[unit]));
     }
     #[should_panic]
     #[test]
@@ -167,21 +298,21 @@ mod tests_from_single_char {
 #[cfg(test)]
 mod tests_from_double_half_char {
     use super::*;
-    use super::test_util::get_string_without_style;
+    use crate::testing::string_without_style;
     #[test]
     fn pass_only_ascii() {
         let unit = DrawableUnit::from_double_half_char('a', 'b', UnitColor::White);
-        assert_eq!("ab", get_string_without_style(&[unit]));
+        assert_eq!("ab", string_without_style(&[unit]));
     }
     #[test]
     fn pass_only_non_ascii() {
         let unit = DrawableUnit::from_double_half_char('●', '◎', UnitColor::White);
-        assert_eq!("●◎", get_string_without_style(&[unit]));
+        assert_eq!("●◎", string_without_style(&[unit]));
     }
     #[test]
     fn pass_ascii_and_non_ascii() {
         let unit = DrawableUnit::from_double_half_char('a', '◎', UnitColor::White);
-        assert_eq!("a◎", get_string_without_style(&[unit]));
+        assert_eq!("a◎", string_without_style(&[unit]));
     }
     #[should_panic]
     #[test]
@@ -195,29 +326,142 @@ mod tests_from_double_half_char {
     }
 }
 
+#[cfg(test)]
+mod tests_with_background {
+    use super::*;
+    #[test]
+    fn background_is_kept_separately() {
+        let unit = DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red);
+        assert_eq!(None, unit.background());
+        let unit = unit.with_background(UnitColor::Blue);
+        assert_eq!(UnitColor::Red, unit.color());
+        assert_eq!(Some(UnitColor::Blue), unit.background());
+        assert_ne!(
+            DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red),
+            unit
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_with_attributes {
+    use super::*;
+    #[test]
+    fn attributes_are_kept() {
+        let unit = DrawableUnit::from_single_full_char('あ', UnitColor::Red);
+        assert!(unit.attributes().is_empty());
+        let unit = unit.with_attributes(UnitAttributes::REVERSE | UnitAttributes::BLINK);
+        assert_eq!(
+            UnitAttributes::REVERSE | UnitAttributes::BLINK,
+            unit.attributes()
+        );
+        assert_eq!(UnitColor::Red, unit.color());
+    }
+}
+
+#[cfg(test)]
+mod tests_write_to {
+    use super::*;
+    fn written(unit: DrawableUnit, color_support: ColorSupport) -> String {
+        let mut s = String::new();
+        unit.write_with_color_support_to(&mut s, color_support)
+            .unwrap();
+        s
+    }
+    #[test]
+    fn basic_color() {
+        let unit = DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red);
+        assert_eq!(
+            "\x1b[0;31mab\x1b[0m",
+            written(unit, ColorSupport::TrueColor)
+        );
+    }
+    #[test]
+    fn extended_colors_and_attributes() {
+        let unit = DrawableUnit::from_single_full_char('あ', UnitColor::Ansi256(208))
+            .with_background(UnitColor::Rgb(0, 64, 255))
+            .with_attributes(UnitAttributes::BOLD | UnitAttributes::REVERSE);
+        assert_eq!(
+            "\x1b[0;38;5;208;48;2;0;64;255;1;7mあ\x1b[0m",
+            written(unit, ColorSupport::TrueColor)
+        );
+        assert_eq!(
+            "\x1b[0;38;5;208;48;5;27;1;7mあ\x1b[0m",
+            written(unit, ColorSupport::Ansi256)
+        );
+        assert_eq!(
+            "\x1b[0;33;44;1;7mあ\x1b[0m",
+            written(unit, ColorSupport::Ansi16)
+        );
+    }
+    #[test]
+    fn monochrome() {
+        let unit = DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red)
+            .with_background(UnitColor::Blue)
+            .with_attributes(UnitAttributes::UNDERLINE);
+        // 色は書き込まれないが，装飾は保たれる
+        assert_eq!(
+            "\x1b[0;4mab\x1b[0m",
+            written(unit, ColorSupport::Monochrome)
+        );
+        let plain = DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red)
+            .with_background(UnitColor::Blue);
+        assert_eq!("ab", written(plain, ColorSupport::Monochrome));
+    }
+}
+
+#[cfg(test)]
+mod tests_has_valid_width {
+    use super::*;
+    #[test]
+    fn valid_units() {
+        assert!(DrawableUnit::from_single_full_char('あ', UnitColor::White).has_valid_width());
+        assert!(DrawableUnit::from_double_half_char('a', '◎', UnitColor::White).has_valid_width());
+    }
+    #[test]
+    fn invalid_units() {
+        let full = DrawableUnit {
+            left: 'a',
+            right: None,
+            color: UnitColor::White,
+            background: None,
+            attributes: UnitAttributes::NONE,
+        };
+        assert!(!full.has_valid_width());
+        let half = DrawableUnit {
+            left: 'a',
+            right: Some('\t'),
+            color: UnitColor::White,
+            background: None,
+            attributes: UnitAttributes::NONE,
+        };
+        assert!(!half.has_valid_width());
+    }
+}
+
 #[cfg(test)]
 mod tests_create_units_from {
     use super::*;
-    use super::test_util::get_string_without_style;
+    use crate::testing::string_without_style;
     #[test]
     fn only_ascii() {
         let units = DrawableUnit::create_units_from("abcdef", UnitColor::White);
-        assert_eq!("abcdef", get_string_without_style(&units));
+        assert_eq!("abcdef", string_without_style(&units));
     }
     #[test]
     fn only_ascii_with_last_whitespace() {
         let units = DrawableUnit::create_units_from("abcdefg", UnitColor::White);
-        assert_eq!("abcdefg ", get_string_without_style(&units));
+        assert_eq!("abcdefg ", string_without_style(&units));
     }
     #[test]
     fn only_full_char() {
         let units = DrawableUnit::create_units_from("あいうえお", UnitColor::White);
-        assert_eq!("あいうえお", get_string_without_style(&units));
+        assert_eq!("あいうえお", string_without_style(&units));
     }
     #[test]
     fn combining_half_and_full_char() {
         let units = DrawableUnit::create_units_from("あaいiuうe", UnitColor::White);
-        assert_eq!("あa いiuうe ", get_string_without_style(&units));
+        assert_eq!("あa いiuうe ", string_without_style(&units));
     }
     #[should_panic]
     #[test]
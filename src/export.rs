@@ -0,0 +1,50 @@
+//! キャンバスの内容を，端末以外の形式で出力する機能を提供する．
+//! 各形式への出力は，`Canvas`のメソッドとして提供される．
+
+mod font;
+mod html;
+mod png;
+mod svg;
+
+use crate::{DrawDestination, DrawableUnit, UnitAttributes, UnitColor};
+use std::fmt;
+
+/// 文字色が指定されていない場合の文字色．
+const DEFAULT_FOREGROUND: UnitColor = UnitColor::White;
+/// 背景色が指定されていない場合の背景色．
+const DEFAULT_BACKGROUND: UnitColor = UnitColor::Black;
+
+/// 画像として出力する際の，描画単位1つあたりの幅(ピクセル)．半角文字1つはこの半分の幅を占める．
+const CELL_WIDTH: usize = 16;
+/// 画像として出力する際の，描画単位1つあたりの高さ(ピクセル)．
+const CELL_HEIGHT: usize = 16;
+
+/// 反転属性を考慮した，描画単位の文字色と背景色を返す．
+fn displayed_colors(unit: &DrawableUnit) -> (UnitColor, Option<UnitColor>) {
+    if unit.attributes().contains(UnitAttributes::REVERSE) {
+        (
+            unit.background().unwrap_or(DEFAULT_BACKGROUND),
+            Some(unit.color()),
+        )
+    } else {
+        (unit.color(), unit.background())
+    }
+}
+
+/// 色をCSSおよびSVGの色指定として表したものを返す．
+fn css_color(color: UnitColor) -> String {
+    let (r, g, b) = color.rgb();
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// HTMLおよびSVGの中で特別な意味を持つ文字を実体参照に置き換えて書き込む．
+fn write_escaped_char<D: DrawDestination>(c: char, destination: &mut D) -> fmt::Result {
+    match c {
+        '&' => destination.write_str("&amp;"),
+        '<' => destination.write_str("&lt;"),
+        '>' => destination.write_str("&gt;"),
+        '"' => destination.write_str("&quot;"),
+        '\'' => destination.write_str("&#39;"),
+        c => destination.write_char(c),
+    }
+}
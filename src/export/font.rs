@@ -0,0 +1,365 @@
+//! 画像出力に用いる，同梱のビットマップフォント．
+//! 半角文字は幅8ピクセル，全角文字は幅16ピクセル，高さはいずれも16ピクセルである．
+//! 全角文字は，全角英数字と記号，ひらがな，カタカナ，および小学校1年で学習する漢字を収録する．
+//! 収録されていない文字は，文字の幅に応じた四角形として描画される．
+
+mod stroke;
+
+use super::{CELL_HEIGHT, CELL_WIDTH};
+use stroke::stroke_glyph;
+
+/// 半角文字1つの幅(ピクセル)．
+const HALF_WIDTH: usize = CELL_WIDTH / 2;
+
+/// 1文字分のビットマップ．各行の最下位ビットが左端のピクセルを表す．
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Glyph {
+    width: usize,
+    rows: [u16; CELL_HEIGHT],
+}
+
+impl Glyph {
+    /// 何も描画されていない，指定した幅のビットマップを返す．
+    const fn blank(width: usize) -> Self {
+        Self {
+            width,
+            rows: [0; CELL_HEIGHT],
+        }
+    }
+
+    /// このビットマップの幅を返す．
+    pub(super) const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// 指定した位置のピクセルが描画されるか返す．
+    pub(super) fn is_set(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < CELL_HEIGHT && self.rows[y] & (1 << x) != 0
+    }
+
+    /// 指定した範囲(両端を含む)のピクセルを描画する．
+    fn fill(&mut self, left: usize, right: usize, top: usize, bottom: usize) {
+        for y in top..=bottom {
+            for x in left..=right {
+                self.rows[y] |= 1 << x;
+            }
+        }
+    }
+
+    /// 収録されていない文字の代わりに描画する四角形を返す．
+    fn missing(width: usize) -> Self {
+        let mut glyph = Self::blank(width);
+        glyph.fill(1, width - 2, 1, 1);
+        glyph.fill(1, width - 2, CELL_HEIGHT - 2, CELL_HEIGHT - 2);
+        glyph.fill(1, 1, 1, CELL_HEIGHT - 2);
+        glyph.fill(width - 2, width - 2, 1, CELL_HEIGHT - 2);
+        glyph
+    }
+}
+
+/// 半角文字のビットマップを返す．収録されていない文字の場合は四角形を返す．
+pub(super) fn half_glyph(c: char) -> Glyph {
+    ascii_glyph(c, 1)
+        .or_else(|| box_drawing_glyph(c))
+        .unwrap_or_else(|| Glyph::missing(HALF_WIDTH))
+}
+
+/// 全角文字のビットマップを返す．収録されていない文字の場合は四角形を返す．
+pub(super) fn full_glyph(c: char) -> Glyph {
+    match c {
+        // 全角英数字および記号は，対応するASCII文字を横に2倍して描画する
+        '\u{ff01}'..='\u{ff5e}' => {
+            let ascii = std::char::from_u32(c as u32 - 0xfee0).expect("Always valid ASCII.");
+            ascii_glyph(ascii, 2).unwrap_or_else(|| Glyph::missing(CELL_WIDTH))
+        }
+        '\u{3000}' => Glyph::blank(CELL_WIDTH),
+        _ => cjk_symbol_glyph(c)
+            .or_else(|| stroke_glyph(c))
+            .unwrap_or_else(|| Glyph::missing(CELL_WIDTH)),
+    }
+}
+
+/// ASCII文字のビットマップを，横方向に指定した倍率で拡大して返す．縦方向には常に2倍に拡大される．
+fn ascii_glyph(c: char, horizontal_scale: usize) -> Option<Glyph> {
+    let code = c as usize;
+    if !(0x20..=0x7e).contains(&code) {
+        return None;
+    }
+    let bitmap = &ASCII_BITMAPS[code - 0x20];
+    let mut glyph = Glyph::blank(HALF_WIDTH * horizontal_scale);
+    for (y, row) in glyph.rows.iter_mut().enumerate() {
+        let source = bitmap[y / 2];
+        for x in 0..HALF_WIDTH * horizontal_scale {
+            if source & (1 << (x / horizontal_scale)) != 0 {
+                *row |= 1 << x;
+            }
+        }
+    }
+    Some(glyph)
+}
+
+/// 罫線素片(U+2500からU+257F)のビットマップを返す．
+fn box_drawing_glyph(c: char) -> Option<Glyph> {
+    let code = c as usize;
+    if !(0x2500..=0x257f).contains(&code) {
+        return None;
+    }
+    let mut glyph = Glyph::blank(HALF_WIDTH);
+    match c {
+        '╱' | '╲' | '╳' => {
+            for y in 0..CELL_HEIGHT {
+                let x = y * HALF_WIDTH / CELL_HEIGHT;
+                if c != '╲' {
+                    glyph.rows[y] |= 1 << (HALF_WIDTH - 1 - x);
+                }
+                if c != '╱' {
+                    glyph.rows[y] |= 1 << x;
+                }
+            }
+        }
+        _ => {
+            let segments = BOX_DRAWING_SEGMENTS[code - 0x2500].as_bytes();
+            let center_x = HALF_WIDTH / 2 - 1;
+            let center_y = CELL_HEIGHT / 2 - 1;
+            // 各線の太さに応じて，中心からのずれの一覧を求める
+            let offsets = |weight: u8| -> &'static [isize] {
+                match weight {
+                    b'l' => &[0],
+                    b'h' => &[-1, 0, 1],
+                    b'd' => &[-2, 2],
+                    _ => &[],
+                }
+            };
+            let shift = |center: usize, offset: isize| (center as isize + offset) as usize;
+            for &offset in offsets(segments[0]) {
+                let y = shift(center_y, offset);
+                glyph.fill(0, center_x, y, y);
+            }
+            for &offset in offsets(segments[1]) {
+                let y = shift(center_y, offset);
+                glyph.fill(center_x, HALF_WIDTH - 1, y, y);
+            }
+            for &offset in offsets(segments[2]) {
+                let x = shift(center_x, offset);
+                glyph.fill(x, x, 0, center_y);
+            }
+            for &offset in offsets(segments[3]) {
+                let x = shift(center_x, offset);
+                glyph.fill(x, x, center_y, CELL_HEIGHT - 1);
+            }
+        }
+    }
+    Some(glyph)
+}
+
+/// 日本語の文章やゲーム画面でよく使われる全角記号のビットマップを返す．
+fn cjk_symbol_glyph(c: char) -> Option<Glyph> {
+    let mut glyph = Glyph::blank(CELL_WIDTH);
+    match c {
+        // 長音記号
+        'ー' => glyph.fill(2, 13, 7, 8),
+        // 全角マクロン(既定の枠の下辺に使われる)
+        '￣' => glyph.fill(0, 15, 0, 1),
+        // 中黒
+        '・' => glyph.fill(7, 8, 7, 8),
+        // 読点
+        '、' => {
+            glyph.fill(3, 4, 11, 12);
+            glyph.fill(5, 6, 13, 14);
+        }
+        // 句点
+        '。' => {
+            glyph.fill(3, 6, 10, 10);
+            glyph.fill(3, 6, 15, 15);
+            glyph.fill(2, 2, 11, 14);
+            glyph.fill(7, 7, 11, 14);
+        }
+        '「' => {
+            glyph.fill(5, 11, 1, 1);
+            glyph.fill(5, 5, 1, 12);
+        }
+        '」' => {
+            glyph.fill(10, 10, 3, 14);
+            glyph.fill(4, 10, 14, 14);
+        }
+        _ => return None,
+    }
+    Some(glyph)
+}
+
+/// 罫線素片の各文字について，左，右，上，下の各方向へ伸びる線の種類を表す．
+/// `.`は線なし，`l`は細線，`h`は太線，`d`は二重線を表す．斜線(U+2571からU+2573)は別途描画される．
+const BOX_DRAWING_SEGMENTS: [&str; 0x80] = [
+    "ll..", "hh..", "..ll", "..hh", "ll..", "hh..", "..ll", "..hh", // U+2500
+    "ll..", "hh..", "..ll", "..hh", ".l.l", ".h.l", ".l.h", ".h.h", // U+2508
+    "l..l", "h..l", "l..h", "h..h", ".ll.", ".hl.", ".lh.", ".hh.", // U+2510
+    "l.l.", "h.l.", "l.h.", "h.h.", ".lll", ".hll", ".lhl", ".llh", // U+2518
+    ".lhh", ".hhl", ".hlh", ".hhh", "l.ll", "h.ll", "l.hl", "l.lh", // U+2520
+    "l.hh", "h.hl", "h.lh", "h.hh", "ll.l", "hl.l", "lh.l", "hh.l", // U+2528
+    "ll.h", "hl.h", "lh.h", "hh.h", "lll.", "hll.", "lhl.", "hhl.", // U+2530
+    "llh.", "hlh.", "lhh.", "hhh.", "llll", "hlll", "lhll", "hhll", // U+2538
+    "llhl", "lllh", "llhh", "hlhl", "lhhl", "hllh", "lhlh", "hhhl", // U+2540
+    "hhlh", "hlhh", "lhhh", "hhhh", "ll..", "hh..", "..ll", "..hh", // U+2548
+    "dd..", "..dd", ".d.l", ".l.d", ".d.d", "d..l", "l..d", "d..d", // U+2550
+    ".dl.", ".ld.", ".dd.", "d.l.", "l.d.", "d.d.", ".dll", ".ldd", // U+2558
+    ".ddd", "d.ll", "l.dd", "d.dd", "dd.l", "ll.d", "dd.d", "ddl.", // U+2560
+    "lld.", "ddd.", "ddll", "lldd", "dddd", ".l.l", "l..l", "l.l.", // U+2568
+    ".ll.", "....", "....", "....", "l...", "..l.", ".l..", "...l", // U+2570
+    "h...", "..h.", ".h..", "...h", "lh..", "..lh", "hl..", "..hl", // U+2578
+];
+
+/// ASCII文字(U+0020からU+007E)の8x8ピクセルのビットマップ．
+/// パブリックドメインのfont8x8に基づく．各バイトの最下位ビットが左端のピクセルを表す．
+const ASCII_BITMAPS: [[u8; 8]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], // '!'
+    [0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], // '#'
+    [0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], // '$'
+    [0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], // '%'
+    [0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], // '&'
+    [0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], // '''
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], // '('
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], // ')'
+    [0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], // '*'
+    [0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ','
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], // '.'
+    [0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], // '/'
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], // '0'
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], // '1'
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], // '2'
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], // '3'
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], // '4'
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], // '5'
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], // '6'
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], // '7'
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], // '8'
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], // '9'
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], // ':'
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ';'
+    [0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], // '<'
+    [0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], // '='
+    [0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], // '>'
+    [0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], // '?'
+    [0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], // '@'
+    [0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], // 'A'
+    [0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], // 'B'
+    [0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], // 'C'
+    [0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], // 'D'
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], // 'E'
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], // 'F'
+    [0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], // 'G'
+    [0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], // 'H'
+    [0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'I'
+    [0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], // 'J'
+    [0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], // 'K'
+    [0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], // 'L'
+    [0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], // 'M'
+    [0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], // 'N'
+    [0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], // 'O'
+    [0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], // 'P'
+    [0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], // 'Q'
+    [0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], // 'R'
+    [0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], // 'S'
+    [0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'T'
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], // 'U'
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // 'V'
+    [0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], // 'W'
+    [0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], // 'X'
+    [0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], // 'Y'
+    [0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], // 'Z'
+    [0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], // '['
+    [0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], // '\'
+    [0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], // ']'
+    [0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], // '_'
+    [0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], // 'a'
+    [0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], // 'b'
+    [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], // 'c'
+    [0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], // 'd'
+    [0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], // 'e'
+    [0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], // 'f'
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], // 'g'
+    [0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], // 'h'
+    [0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'i'
+    [0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], // 'j'
+    [0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], // 'k'
+    [0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'l'
+    [0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], // 'm'
+    [0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], // 'n'
+    [0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], // 'o'
+    [0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], // 'p'
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], // 'q'
+    [0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], // 'r'
+    [0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], // 's'
+    [0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], // 't'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], // 'u'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // 'v'
+    [0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], // 'w'
+    [0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], // 'x'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], // 'y'
+    [0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], // 'z'
+    [0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], // '{'
+    [0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], // '|'
+    [0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], // '}'
+    [0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '~'
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_glyph() {
+        let glyph = half_glyph('_');
+        assert_eq!(HALF_WIDTH, glyph.width());
+        // 下線はビットマップの最下行を縦に2倍したもの
+        assert!((0..HALF_WIDTH).all(|x| glyph.is_set(x, 14) && glyph.is_set(x, 15)));
+        assert!((0..14).all(|y| (0..HALF_WIDTH).all(|x| !glyph.is_set(x, y))));
+    }
+
+    #[test]
+    fn test_box_drawing_glyph() {
+        let glyph = half_glyph('─');
+        assert!((0..HALF_WIDTH).all(|x| glyph.is_set(x, 7)));
+        assert!(!glyph.is_set(3, 0));
+        let glyph = half_glyph('┌');
+        assert!(glyph.is_set(HALF_WIDTH - 1, 7));
+        assert!(glyph.is_set(3, CELL_HEIGHT - 1));
+        assert!(!glyph.is_set(0, 7));
+        assert!(!glyph.is_set(3, 0));
+    }
+
+    #[test]
+    fn test_full_width_glyph() {
+        let full = full_glyph('Ａ');
+        assert_eq!(CELL_WIDTH, full.width());
+        let half = half_glyph('A');
+        for y in 0..CELL_HEIGHT {
+            for x in 0..CELL_WIDTH {
+                assert_eq!(half.is_set(x / 2, y), full.is_set(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn kana_and_kanji_are_not_missing() {
+        let missing = Glyph::missing(CELL_WIDTH);
+        let kana = ('\u{3041}'..='\u{3096}').chain('\u{30a1}'..='\u{30fa}');
+        for c in kana.chain("一学森".chars()) {
+            let glyph = full_glyph(c);
+            assert_ne!(missing, glyph, "{}", c);
+            assert!((0..CELL_HEIGHT).any(|y| (0..CELL_WIDTH).any(|x| glyph.is_set(x, y))));
+        }
+        assert_ne!(full_glyph('あ'), full_glyph('ア'));
+    }
+
+    #[test]
+    fn missing_glyph_is_box() {
+        assert_eq!(Glyph::missing(CELL_WIDTH), full_glyph('𠮷'));
+        assert_eq!(Glyph::missing(HALF_WIDTH), half_glyph('◎'));
+    }
+}
@@ -0,0 +1,428 @@
+//! 線分の列で表した全角文字の字形．
+//! ひらがな，カタカナ，および小学校1年で学習する漢字80字を収録する．
+//! 濁音，半濁音および小書きの仮名は，元の仮名の字形から合成される．
+//!
+//! 各字形は空白で区切られた画の列であり，各画は16x16ピクセルの格子上の点の列である．
+//! 点は横，縦の座標をそれぞれ16進数1桁で表した2文字で表され，画の連続する点の間には線分が描画される．
+
+use super::{Glyph, CELL_WIDTH};
+
+/// 仮名の合成方法．
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Modifier {
+    /// 右上に濁点を付加する．
+    Dakuten,
+    /// 右上に半濁点を付加する．
+    Handakuten,
+    /// 4分の3に縮小し，左右の中央かつ下端に寄せる．
+    Small,
+}
+
+/// 濁点の字形．
+const DAKUTEN: &str = "c1d3 e0f2";
+/// 半濁点の字形．
+const HANDAKUTEN: &str = "c0e0f1f3e4c4b3b1c0";
+
+/// 線分の列で表した全角文字の字形を返す．収録されていない文字の場合は`None`を返す．
+pub(super) fn stroke_glyph(c: char) -> Option<Glyph> {
+    if let Some(strokes) = find_strokes(c) {
+        let mut glyph = Glyph::blank(CELL_WIDTH);
+        draw_strokes(&mut glyph, strokes, |x, y| (x, y));
+        return Some(glyph);
+    }
+    let &(_, base, modifier) = find_derived(c)?;
+    let strokes = find_strokes(base).expect("Base of a derived kana always has strokes.");
+    let mut glyph = Glyph::blank(CELL_WIDTH);
+    match modifier {
+        Modifier::Dakuten => {
+            draw_strokes(&mut glyph, strokes, |x, y| (x, y));
+            draw_strokes(&mut glyph, DAKUTEN, |x, y| (x, y));
+        }
+        Modifier::Handakuten => {
+            draw_strokes(&mut glyph, strokes, |x, y| (x, y));
+            draw_strokes(&mut glyph, HANDAKUTEN, |x, y| (x, y));
+        }
+        // 4分の3に縮小し，左右に2ピクセルずつの余白を設けて下端を揃える
+        Modifier::Small => draw_strokes(&mut glyph, strokes, |x, y| (2 + x * 3 / 4, 4 + y * 3 / 4)),
+    }
+    Some(glyph)
+}
+
+/// 指定した文字の，線分の列で表した字形を探す．
+fn find_strokes(c: char) -> Option<&'static str> {
+    STROKES
+        .binary_search_by_key(&c, |&(key, _)| key)
+        .ok()
+        .map(|index| STROKES[index].1)
+}
+
+/// 合成される仮名の表から，指定した文字の合成方法を探す．
+fn find_derived(c: char) -> Option<&'static (char, char, Modifier)> {
+    DERIVED
+        .binary_search_by_key(&c, |&(key, _, _)| key)
+        .ok()
+        .map(|index| &DERIVED[index])
+}
+
+/// 字形を表す文字列に従って，各画を描画する．各点の座標は`transform`で変換される．
+fn draw_strokes<F>(glyph: &mut Glyph, strokes: &str, transform: F)
+where
+    F: Fn(usize, usize) -> (usize, usize),
+{
+    for stroke in strokes.split(' ') {
+        let points = stroke
+            .as_bytes()
+            .chunks(2)
+            .map(|point| transform(hex_digit(point[0]), hex_digit(point[1])))
+            .collect::<Vec<_>>();
+        if let [point] = points.as_slice() {
+            draw_line(glyph, *point, *point);
+        }
+        for segment in points.windows(2) {
+            draw_line(glyph, segment[0], segment[1]);
+        }
+    }
+}
+
+/// 16進数1桁を表すASCII文字の値を返す．
+fn hex_digit(digit: u8) -> usize {
+    (digit as char)
+        .to_digit(16)
+        .expect("Stroke data consists of hexadecimal digits.") as usize
+}
+
+/// 2点を結ぶ線分を，Bresenhamのアルゴリズムで描画する．
+fn draw_line(glyph: &mut Glyph, from: (usize, usize), to: (usize, usize)) {
+    let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+    let (to_x, to_y) = (to.0 as isize, to.1 as isize);
+    let dx = (to_x - x).abs();
+    let dy = -(to_y - y).abs();
+    let step_x = if x < to_x { 1 } else { -1 };
+    let step_y = if y < to_y { 1 } else { -1 };
+    let mut error = dx + dy;
+    loop {
+        glyph.fill(x as usize, x as usize, y as usize, y as usize);
+        if x == to_x && y == to_y {
+            break;
+        }
+        let doubled_error = 2 * error;
+        if doubled_error >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled_error <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// 線分の列で表した字形の一覧．文字の昇順に並ぶ．
+const STROKES: [(char, &str); 181] = [
+    // 記号
+    ('々', "5126 44c45e 689b"),
+    // ひらがな
+    ('あ', "23c2 616a8d a59a5d3d2b2856a6d8dbad"),
+    ('い', "332a4d5c b5da"),
+    ('う', "62a3 4795b6c9ad6e"),
+    ('え', "63a4 37b63e7a9ede"),
+    ('お', "2484 515d4e2e1c2a58a7d9dcae b2d4"),
+    ('か', "25a4b6ab7e 612e c3e7"),
+    ('き', "34b3 38c7 61ba 4b5d7ebe"),
+    ('く', "a138ae"),
+    ('け', "222b3e 65d5 a1aa9d7e"),
+    ('こ', "44b396 3a4c6dcd"),
+    ('さ', "35c4 62ac 495c8ece"),
+    ('し', "424b5d8ecb"),
+    ('す', "25e5 81897a5957788a8c6f"),
+    ('せ', "15e4 b2b99a 525c6ede"),
+    ('そ', "42a238d7897c9ece"),
+    ('た', "2494 612e 96d6 8a9dde"),
+    ('ち', "24b4 614887c8dbae6e"),
+    ('つ', "2784c5d9ad6e"),
+    ('て', "24d375595c8ebe"),
+    ('と', "4168 b4483b5ede"),
+    ('な', "2484 512c b4d6 a7ab9d6d5b7aaade"),
+    ('に', "222b3e 63c3 6a6c8ddd"),
+    ('ぬ', "236c 812c2764a4d7dcbe9d9bbbee"),
+    ('ね', "313e 16652e97c6d9dcae8d9bbcee"),
+    ('の', "847a4d2d1b174382c3e6ebbd8e"),
+    ('は', "222c3e 65e5 a1ab8d6d6b8aaade"),
+    ('ひ', "24633a4d7db9c3e8"),
+    ('ふ', "6294 869b6d 3a1d baed"),
+    ('へ', "1a55ec"),
+    ('ほ', "222c3e 63d3 67d7 a3ab8d6d6b8aaade"),
+    ('ま', "34c4 38c8 818b6d4c5a8aacde"),
+    ('み', "23833b3d5d99e9 b69e"),
+    ('む', "25a5 51593a28485a5c7ebeca c3e5"),
+    ('め', "336c a13d2c2865a4d7dcae8e"),
+    ('も', "615b6d8ebeda 25a5 29a9"),
+    ('や', "28b5e7e9bb 8293 418e"),
+    ('ゆ', "232c4694c5d9bc8b 818b6e"),
+    ('よ', "717b5d3c4a7a9bde 75c5"),
+    ('ら', "5384 353a5898c9dcbe6e"),
+    ('り', "323a4b a2aa8d5e"),
+    ('る', "33b33c89c9dcae7e6c8b"),
+    ('れ', "313e 16652e86a6acbeec"),
+    ('ろ', "33b33c89c9dcae6e"),
+    ('わ', "313e 16652e86c6e9ecbe9e"),
+    ('ゐ', "32b34c2d1b38a8dadcbe9e8c"),
+    ('ゑ', "33a36776 28a8 3b5a7c9ab9dd 1dde"),
+    ('を', "24a4 512957 97aa d65b5d7ede"),
+    ('ん', "812e58788daee9"),
+    ('゛', DAKUTEN),
+    ('゜', HANDAKUTEN),
+    ('ゝ', "44a94d"),
+    // カタカナ
+    ('ア', "22d2a6 75793e"),
+    ('イ', "c119 757e"),
+    ('ウ', "7174 2824d4d8ad6e"),
+    ('エ', "34c4 747c 1cec"),
+    ('オ', "25e5 a1ae7e 962d"),
+    ('カ', "25d5dcae9d 71692e"),
+    ('キ', "35c4 29e8 719e"),
+    ('ク', "6127 54d4b95e"),
+    ('ケ', "6127 55e5 a59a6e"),
+    ('コ', "33c3cc 3ccc"),
+    ('サ', "15e5 515a b1b96e"),
+    ('シ', "2254 1748 3ed4"),
+    ('ス', "33c31e 89ee"),
+    ('セ', "16e4b8 515c7ede"),
+    ('ソ', "2357 c25e"),
+    ('タ', "6127 54d45e 58bb"),
+    ('チ', "c133 17e7 838a5e"),
+    ('ツ', "2347 6386 d25e"),
+    ('テ', "32c2 16e6 868a5e"),
+    ('ト', "515e 66c9"),
+    ('ナ', "15e5 818a4e"),
+    ('ニ', "34c4 1beb"),
+    ('ヌ', "33c31e 67dd"),
+    ('ネ', "7173 34c41c 888e a9dc"),
+    ('ノ', "c1ba2e"),
+    ('ハ', "531c 93ec"),
+    ('ヒ', "313c5ede 37c4"),
+    ('フ', "23d3b95e"),
+    ('ヘ', "1a55ec"),
+    ('ホ', "15e5 818e6d 481c b8ec"),
+    ('マ', "23d379 57ad"),
+    ('ミ', "42b4 36a8 2bdd"),
+    ('ム', "612ddc a8ee"),
+    ('メ', "c12e 45dc"),
+    ('モ', "33c3 17e7 636c8ede"),
+    ('ヤ', "16e4b8 518e"),
+    ('ユ', "34b4bc 1cec"),
+    ('ヨ', "33c3cd 37c7 3dcd"),
+    ('ラ', "32c2 26d6ba5e"),
+    ('リ', "323a b1ba6e"),
+    ('ル', "515a2e 919ee9"),
+    ('レ', "313ee7"),
+    ('ロ', "333d 33c3cd 3dcd"),
+    ('ワ', "2328 23d3b95e"),
+    ('ヰ', "15e5 1aea a1ae 5159"),
+    ('ヱ', "33c397 878d 1ded"),
+    ('ヲ', "23d3b95e 27c7"),
+    ('ン', "2255 3ee5"),
+    ('ヽ', "44bb"),
+    // 漢字
+    ('一', "18e8"),
+    ('七', "18e6 515c7eddda"),
+    ('三', "33c3 48b8 1ded"),
+    ('上', "717d 77c7 1ded"),
+    ('下', "12e2 727e 86b9"),
+    ('中', "242b 24d4db 2bdb 707f"),
+    ('九', "71775b1e 25a5acceeeeb"),
+    ('二', "34c4 1cec"),
+    ('五', "22d2 625d 37b7bd 1ded"),
+    ('人', "71671e 76ee"),
+    ('休', "4107 242e 55f5 a1ae a66c b6fc"),
+    ('先', "4125 33c3 7077 17e7 575a1e 979dbeeeec"),
+    ('入', "831e 5196ee"),
+    ('八', "53491d 93a8ed"),
+    ('六', "7183 15e5 592e a9de"),
+    ('円', "2e22 22d2debe 7277 37c7"),
+    ('出', "707e 3237c7c2 292eded9"),
+    ('力', "25c5cdad 71671e"),
+    ('十', "18e8 818e"),
+    ('千', "c133 17e7 838e"),
+    ('口', "232d 23d3dd 2ddd"),
+    ('右', "14e4 812e 595e 59d9de 5ede"),
+    ('名', "6126 53b33a 5587 686e 68d8de 6ede"),
+    ('四', "222e 22d2de 2ede 62674a 9299b9"),
+    ('土', "38c8 727d 1ded"),
+    ('夕', "6127 54c45e 5799"),
+    ('大', "15e5 71761e 87ee"),
+    ('天', "32c2 17e7 72781e 89ee"),
+    ('女', "6138de b62e 17e7"),
+    ('子', "33b377 777e5e 19e9"),
+    ('字', "7072 2422d2d4 45b578 787e5e 1aea"),
+    ('学', "3042 7082 c0b2 2523d3d5 46b679 797e5e 1aea"),
+    ('小', "717d5e 451a a5da"),
+    ('山', "717d 232d c3cd 2dcd"),
+    ('川', "323a1e 727c b1be"),
+    ('左', "14e4 812e 68d8 989d 5eee"),
+    ('年', "5115 33d3 36c6 363a 1aea 828f"),
+    ('手', "c032 25d5 19e9 727e5e"),
+    ('文', "7072 13e3 44de b41e"),
+    ('日', "323e 32c2ce 38c8 3ece"),
+    ('早', "313a 31c1ca 35c5 3aca 1ded 7a7f"),
+    ('月', "313a1e 31c1cdae 35c5 39c9"),
+    ('木', "15e5 717e 751c 85ec"),
+    ('本', "15e5 717e 751c 85ec 4bab"),
+    ('村', "0464 314e 3509 4668 75f5 c1cdae 898a"),
+    ('林', "1464 414e 4409 4468 84f4 b1be b48b c4fb"),
+    ('校', "0464 314e 3509 4668 b0b2 73f3 9486 d4e6 87ee e77e"),
+    (
+        '森',
+        "33c3 7077 7337 8337 0a6a 383f 3b0e 4b6d 8afa b8bf bb8e cbfe",
+    ),
+    ('正', "22d2 727e 77c7 363e 1eee"),
+    ('気', "5126 53d3 56c6 38b8bddeeb 5a8d 8a5d"),
+    ('水', "717d5e 25551b c387ed"),
+    ('火', "3417 c4a7 71781e 89ee"),
+    ('犬', "15e5 71761e 87ee a1c3"),
+    ('玉', "23c3 38b8 1ded 737d aacc"),
+    ('王', "23c3 38b8 1ded 737d"),
+    ('生', "4117 25d5 39c9 717e 1eee"),
+    ('田', "222e 22d2de 2ede 28d8 727e"),
+    ('男', "3137 31c1c7 37c7 34c4 7177 2adadebe 781f"),
+    ('町', "121a 12626a 1a6a 1666 424a 83f3 c3cdad"),
+    ('白', "7062 333e 33c3ce 38c8 3ece"),
+    ('百', "11e1 7164 343e 34c4ce 39c9 3ece"),
+    ('目', "313e 31c1ce 35c5 39c9 3ece"),
+    ('石', "12e2 622b 585e 58d8de 5ede"),
+    ('空', "7071 2422d2d4 5427 9496d7 49b9 797e 2ede"),
+    ('立', "7072 23d3 4659 b69b 1ded"),
+    ('竹', "4115 3373 434e a185 93e3 c3ceae"),
+    ('糸', "61346665 93263dd9 a7c9 797f 4b2e abde"),
+    ('耳', "11e1 414c a1af 45a5 49a9 1ded"),
+    ('花', "12e2 5054 a0a4 552a 383f c679 858daeeeec"),
+    ('草', "12e2 4044 a0a4 454a 45b5ba 47b7 4aba 1cec 7a7f"),
+    ('虫', "232a 23d3da 2ada 707d 1ddb abde"),
+    ('見', "3039 30c0c9 33c3 36c6 39c9 695c1e 999dbeeeec"),
+    ('貝', "303a 30c0ca 33c3 36c6 3aca 5b2e abde"),
+    ('赤', "33c3 7076 16e6 584c2e a8aeae 291b c9eb"),
+    ('足', "3136 31c1c6 36c6 767d 89c9 494c2e 4cee"),
+    ('車', "12e2 343a 34c4ca 37c7 3aca 1cec 707f"),
+    ('金', "811a 81ea 47b7 3aca 1eee 777e 4b5d bbad"),
+    ('雨', "11e1 242e 24d4debe 717c 4657 4a5b a6b7 aabb"),
+    ('青', "7075 21d1 33c3 15e5 474d3e 47b7be9e 49b9 4bbb"),
+    ('音', "7071 32c2 5355 a3a4 15e5 373e 37c7ce 3aca 3ece"),
+];
+
+/// 他の仮名の字形から合成される仮名の一覧．文字の昇順に並ぶ．
+/// 各要素は，合成される文字，元の仮名，および合成方法の組である．
+const DERIVED: [(char, char, Modifier); 82] = [
+    ('ぁ', 'あ', Modifier::Small),
+    ('ぃ', 'い', Modifier::Small),
+    ('ぅ', 'う', Modifier::Small),
+    ('ぇ', 'え', Modifier::Small),
+    ('ぉ', 'お', Modifier::Small),
+    ('が', 'か', Modifier::Dakuten),
+    ('ぎ', 'き', Modifier::Dakuten),
+    ('ぐ', 'く', Modifier::Dakuten),
+    ('げ', 'け', Modifier::Dakuten),
+    ('ご', 'こ', Modifier::Dakuten),
+    ('ざ', 'さ', Modifier::Dakuten),
+    ('じ', 'し', Modifier::Dakuten),
+    ('ず', 'す', Modifier::Dakuten),
+    ('ぜ', 'せ', Modifier::Dakuten),
+    ('ぞ', 'そ', Modifier::Dakuten),
+    ('だ', 'た', Modifier::Dakuten),
+    ('ぢ', 'ち', Modifier::Dakuten),
+    ('っ', 'つ', Modifier::Small),
+    ('づ', 'つ', Modifier::Dakuten),
+    ('で', 'て', Modifier::Dakuten),
+    ('ど', 'と', Modifier::Dakuten),
+    ('ば', 'は', Modifier::Dakuten),
+    ('ぱ', 'は', Modifier::Handakuten),
+    ('び', 'ひ', Modifier::Dakuten),
+    ('ぴ', 'ひ', Modifier::Handakuten),
+    ('ぶ', 'ふ', Modifier::Dakuten),
+    ('ぷ', 'ふ', Modifier::Handakuten),
+    ('べ', 'へ', Modifier::Dakuten),
+    ('ぺ', 'へ', Modifier::Handakuten),
+    ('ぼ', 'ほ', Modifier::Dakuten),
+    ('ぽ', 'ほ', Modifier::Handakuten),
+    ('ゃ', 'や', Modifier::Small),
+    ('ゅ', 'ゆ', Modifier::Small),
+    ('ょ', 'よ', Modifier::Small),
+    ('ゎ', 'わ', Modifier::Small),
+    ('ゔ', 'う', Modifier::Dakuten),
+    ('ゕ', 'か', Modifier::Small),
+    ('ゖ', 'け', Modifier::Small),
+    ('ゞ', 'ゝ', Modifier::Dakuten),
+    ('ァ', 'ア', Modifier::Small),
+    ('ィ', 'イ', Modifier::Small),
+    ('ゥ', 'ウ', Modifier::Small),
+    ('ェ', 'エ', Modifier::Small),
+    ('ォ', 'オ', Modifier::Small),
+    ('ガ', 'カ', Modifier::Dakuten),
+    ('ギ', 'キ', Modifier::Dakuten),
+    ('グ', 'ク', Modifier::Dakuten),
+    ('ゲ', 'ケ', Modifier::Dakuten),
+    ('ゴ', 'コ', Modifier::Dakuten),
+    ('ザ', 'サ', Modifier::Dakuten),
+    ('ジ', 'シ', Modifier::Dakuten),
+    ('ズ', 'ス', Modifier::Dakuten),
+    ('ゼ', 'セ', Modifier::Dakuten),
+    ('ゾ', 'ソ', Modifier::Dakuten),
+    ('ダ', 'タ', Modifier::Dakuten),
+    ('ヂ', 'チ', Modifier::Dakuten),
+    ('ッ', 'ツ', Modifier::Small),
+    ('ヅ', 'ツ', Modifier::Dakuten),
+    ('デ', 'テ', Modifier::Dakuten),
+    ('ド', 'ト', Modifier::Dakuten),
+    ('バ', 'ハ', Modifier::Dakuten),
+    ('パ', 'ハ', Modifier::Handakuten),
+    ('ビ', 'ヒ', Modifier::Dakuten),
+    ('ピ', 'ヒ', Modifier::Handakuten),
+    ('ブ', 'フ', Modifier::Dakuten),
+    ('プ', 'フ', Modifier::Handakuten),
+    ('ベ', 'ヘ', Modifier::Dakuten),
+    ('ペ', 'ヘ', Modifier::Handakuten),
+    ('ボ', 'ホ', Modifier::Dakuten),
+    ('ポ', 'ホ', Modifier::Handakuten),
+    ('ャ', 'ヤ', Modifier::Small),
+    ('ュ', 'ユ', Modifier::Small),
+    ('ョ', 'ヨ', Modifier::Small),
+    ('ヮ', 'ワ', Modifier::Small),
+    ('ヴ', 'ウ', Modifier::Dakuten),
+    ('ヵ', 'カ', Modifier::Small),
+    ('ヶ', 'ケ', Modifier::Small),
+    ('ヷ', 'ワ', Modifier::Dakuten),
+    ('ヸ', 'ヰ', Modifier::Dakuten),
+    ('ヹ', 'ヱ', Modifier::Dakuten),
+    ('ヺ', 'ヲ', Modifier::Dakuten),
+    ('ヾ', 'ヽ', Modifier::Dakuten),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::super::CELL_HEIGHT;
+    use super::*;
+
+    #[test]
+    fn tables_are_sorted() {
+        assert!(STROKES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(DERIVED.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(DERIVED
+            .iter()
+            .all(|&(_, base, _)| find_strokes(base).is_some()));
+    }
+
+    #[test]
+    fn derived_kana_contains_base() {
+        let base = stroke_glyph('か').unwrap();
+        let voiced = stroke_glyph('が').unwrap();
+        assert_ne!(base, voiced);
+        for y in 0..CELL_HEIGHT {
+            for x in 0..CELL_WIDTH {
+                assert!(!base.is_set(x, y) || voiced.is_set(x, y));
+            }
+        }
+        assert_ne!(stroke_glyph('ば'), stroke_glyph('ぱ'));
+        // 小書きの仮名は上端に余白を持つ
+        let small = stroke_glyph('ァ').unwrap();
+        assert!((0..4).all(|y| (0..CELL_WIDTH).all(|x| !small.is_set(x, y))));
+    }
+}
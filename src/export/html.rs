@@ -0,0 +1,136 @@
+use super::{
+    css_color, displayed_colors, write_escaped_char, DEFAULT_BACKGROUND, DEFAULT_FOREGROUND,
+};
+use crate::{
+    Canvas, CanvasItemPosition, DrawDestination, DrawError, DrawableUnit, Layer, UnitAttributes,
+};
+use std::fmt;
+
+/// HTML文書の先頭部分．各描画単位を幅2文字分の`span`要素として描画し，正方形の格子を保つ．
+const HTML_HEADER: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Canvas</title>
+<style>
+pre.canvas { display: inline-block; margin: 0; padding: 0.5em; font-family: monospace; line-height: 1.2; }
+pre.canvas span { display: inline-block; width: 2ch; height: 1.2em; overflow: hidden; vertical-align: top; white-space: pre; }
+pre.canvas .blink { animation: blink 1s step-start infinite; }
+@keyframes blink { 50% { visibility: hidden; } }
+</style>
+</head>
+<body>
+"#;
+
+/// HTML文書の末尾部分．
+const HTML_FOOTER: &str = "</pre>\n</body>\n</html>\n";
+
+impl<L: Layer> Canvas<L> {
+    /// このキャンバスの内容を，枠とともに単独で表示可能なHTML文書として書き込む．
+    /// 各描画単位は等幅フォントの2文字分の幅を持つ要素となり，文字色，背景色および文字装飾はCSSで表現される．
+    pub fn write_html_to<D: DrawDestination>(&self, destination: &mut D) -> Result<(), DrawError> {
+        destination.write_str(HTML_HEADER)?;
+        write!(
+            destination,
+            "<pre class=\"canvas\" style=\"color: {}; background-color: {};\">",
+            css_color(DEFAULT_FOREGROUND),
+            css_color(DEFAULT_BACKGROUND)
+        )?;
+        let framed_size = self.framed_size();
+        for y in 0..framed_size.y {
+            if y > 0 {
+                destination.write_char('\n')?;
+            }
+            for x in 0..framed_size.x {
+                write_unit_html(
+                    self.framed_unit_at(CanvasItemPosition::new(x, y)),
+                    destination,
+                )?;
+            }
+        }
+        destination.write_str(HTML_FOOTER)?;
+        Ok(())
+    }
+
+    /// このキャンバスの内容を，枠とともに単独で表示可能なHTML文書として返す．
+    /// 書式は`write_html_to`と同じである．
+    pub fn to_html(&self) -> String {
+        let mut s = String::new();
+        self.write_html_to(&mut s)
+            .expect("Writing to String never fails.");
+        s
+    }
+}
+
+/// 描画単位を1つの`span`要素として書き込む．
+fn write_unit_html<D: DrawDestination>(unit: DrawableUnit, destination: &mut D) -> fmt::Result {
+    let attributes = unit.attributes();
+    let (foreground, background) = displayed_colors(&unit);
+    destination.write_str("<span")?;
+    if attributes.contains(UnitAttributes::BLINK) {
+        destination.write_str(" class=\"blink\"")?;
+    }
+    write!(destination, " style=\"color: {};", css_color(foreground))?;
+    if let Some(background) = background {
+        write!(destination, " background-color: {};", css_color(background))?;
+    }
+    if attributes.contains(UnitAttributes::BOLD) {
+        destination.write_str(" font-weight: bold;")?;
+    }
+    if attributes.contains(UnitAttributes::DIM) {
+        destination.write_str(" opacity: 0.5;")?;
+    }
+    if attributes.contains(UnitAttributes::UNDERLINE) {
+        destination.write_str(" text-decoration: underline;")?;
+    }
+    destination.write_str("\">")?;
+    for c in unit.chars() {
+        write_escaped_char(c, destination)?;
+    }
+    destination.write_str("</span>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BorderStyle, UnitColor};
+    use data_structure::Pair;
+
+    #[test]
+    fn test_to_html() {
+        let mut canvas = Canvas::with_size(Pair::new(2, 1));
+        canvas.set_border_style(BorderStyle::None);
+        let unit = DrawableUnit::from_double_half_char('<', '&', UnitColor::Rgb(255, 128, 0))
+            .with_background(UnitColor::Blue)
+            .with_attributes(UnitAttributes::BOLD | UnitAttributes::BLINK);
+        canvas.draw_unit(unit, CanvasItemPosition::new(1, 0), 0);
+        let html = canvas.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.ends_with("</html>\n"));
+        assert!(html.contains(
+            "<span style=\"color: #e5e5e5;\">  </span>\
+             <span class=\"blink\" style=\"color: #ff8000; background-color: #0000ee; font-weight: bold;\">&lt;&amp;</span></pre>"
+        ));
+    }
+
+    #[test]
+    fn reverse_swaps_colors() {
+        let unit = DrawableUnit::from_single_full_char('あ', UnitColor::Red)
+            .with_attributes(UnitAttributes::REVERSE);
+        let mut s = String::new();
+        write_unit_html(unit, &mut s).unwrap();
+        assert_eq!(
+            "<span style=\"color: #000000; background-color: #cd0000;\">あ</span>",
+            s
+        );
+    }
+
+    #[test]
+    fn rows_are_separated_by_newline() {
+        let canvas = Canvas::<i32>::with_size(Pair::new(1, 1));
+        let html = canvas.to_html();
+        // 枠を含めて3行となる
+        let body = &html[html.find("<pre").unwrap()..html.find("</pre>").unwrap()];
+        assert_eq!(2, body.matches('\n').count());
+    }
+}
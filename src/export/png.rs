@@ -0,0 +1,260 @@
+use super::font::{full_glyph, half_glyph, Glyph};
+use super::{displayed_colors, CELL_HEIGHT, CELL_WIDTH, DEFAULT_BACKGROUND};
+use crate::{Canvas, CanvasItemPosition, DrawError, DrawableUnit, Layer, UnitAttributes};
+use std::io;
+
+/// PNGファイルの先頭に置かれるシグネチャ．
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+/// 無圧縮のdeflateブロック1つに格納できる最大のバイト数．
+const MAX_STORED_BLOCK_LENGTH: usize = 0xffff;
+
+impl<L: Layer> Canvas<L> {
+    /// このキャンバスの内容を，枠とともにPNG画像として書き込む．
+    /// 各描画単位は16x16ピクセルに，同梱のビットマップフォントを用いて描画される．
+    /// フォントに収録されていない文字は四角形として描画される．
+    pub fn write_png_to<W: io::Write>(&self, writer: &mut W) -> Result<(), DrawError> {
+        let framed_size = self.framed_size();
+        let mut image = Image::new(framed_size.x * CELL_WIDTH, framed_size.y * CELL_HEIGHT);
+        for y in 0..framed_size.y {
+            for x in 0..framed_size.x {
+                let position = CanvasItemPosition::new(x, y);
+                image.draw_unit(self.framed_unit_at(position), position);
+            }
+        }
+        writer.write_all(&image.encode_png())?;
+        Ok(())
+    }
+
+    /// このキャンバスの内容を，枠とともにPNG画像として返す．
+    /// 書式は`write_png_to`と同じである．
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.write_png_to(&mut bytes)
+            .expect("Writing to Vec never fails.");
+        bytes
+    }
+}
+
+/// 8ビットRGB形式の画像．
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// 既定の背景色で塗りつぶされた画像を返す．
+    fn new(width: usize, height: usize) -> Self {
+        let (r, g, b) = DEFAULT_BACKGROUND.rgb();
+        let pixels = (0..width * height).flat_map(|_| vec![r, g, b]).collect();
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, (r, g, b): (u8, u8, u8)) {
+        let index = (y * self.width + x) * 3;
+        self.pixels[index..index + 3].copy_from_slice(&[r, g, b]);
+    }
+
+    /// 枠を含めた座標`position`にある描画単位を描画する．
+    fn draw_unit(&mut self, unit: DrawableUnit, position: CanvasItemPosition) {
+        let left = position.x * CELL_WIDTH;
+        let top = position.y * CELL_HEIGHT;
+        let (foreground, background) = displayed_colors(&unit);
+        let attributes = unit.attributes();
+        let mut foreground = foreground.rgb();
+        if attributes.contains(UnitAttributes::DIM) {
+            foreground = (foreground.0 / 2, foreground.1 / 2, foreground.2 / 2);
+        }
+        let background = background.unwrap_or(DEFAULT_BACKGROUND).rgb();
+
+        let chars = unit.chars().collect::<Vec<_>>();
+        let glyphs = match chars.as_slice() {
+            [c] => vec![full_glyph(*c)],
+            _ => chars.iter().map(|&c| half_glyph(c)).collect(),
+        };
+        let bold = attributes.contains(UnitAttributes::BOLD);
+        let underline = attributes.contains(UnitAttributes::UNDERLINE);
+        let mut glyph_left = left;
+        for glyph in glyphs {
+            for y in 0..CELL_HEIGHT {
+                for x in 0..glyph.width() {
+                    let is_set = is_glyph_pixel_set(&glyph, x, y, bold)
+                        || (underline && y == CELL_HEIGHT - 1);
+                    let color = if is_set { foreground } else { background };
+                    self.set_pixel(glyph_left + x, top + y, color);
+                }
+            }
+            glyph_left += glyph.width();
+        }
+    }
+
+    /// この画像をPNG形式で表したバイト列を返す．画像データは圧縮せずに格納される．
+    fn encode_png(&self) -> Vec<u8> {
+        let mut header = vec![];
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // ビット深度8，RGB，deflate，標準のフィルタ，インターレースなし
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        // 各行の先頭にフィルタの種類(フィルタなし)を置く
+        let row_length = self.width * 3;
+        let mut scanlines = Vec::with_capacity((row_length + 1) * self.height);
+        for row in self.pixels.chunks(row_length.max(1)).take(self.height) {
+            scanlines.push(0);
+            scanlines.extend_from_slice(row);
+        }
+
+        let mut bytes = PNG_SIGNATURE.to_vec();
+        write_chunk(&mut bytes, b"IHDR", &header);
+        write_chunk(&mut bytes, b"IDAT", &zlib_stored(&scanlines));
+        write_chunk(&mut bytes, b"IEND", &[]);
+        bytes
+    }
+}
+
+/// 太字の場合は1ピクセル右へずらしたものを重ねて，ビットマップの指定位置が描画されるか返す．
+fn is_glyph_pixel_set(glyph: &Glyph, x: usize, y: usize, bold: bool) -> bool {
+    glyph.is_set(x, y) || (bold && x > 0 && glyph.is_set(x - 1, y))
+}
+
+/// PNGのチャンクを書き込む．
+fn write_chunk(bytes: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = bytes.len();
+    bytes.extend_from_slice(chunk_type);
+    bytes.extend_from_slice(data);
+    let crc = crc32(&bytes[start..]);
+    bytes.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// 無圧縮のdeflateブロックのみからなるzlib形式のデータを返す．
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_STORED_BLOCK_LENGTH).peekable();
+    if blocks.peek().is_none() {
+        bytes.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        bytes.push(is_final as u8);
+        let length = block.len() as u16;
+        bytes.extend_from_slice(&length.to_le_bytes());
+        bytes.extend_from_slice(&(!length).to_le_bytes());
+        bytes.extend_from_slice(block);
+    }
+    bytes.extend_from_slice(&adler32(data).to_be_bytes());
+    bytes
+}
+
+/// PNGのチャンクに用いるCRC-32を計算する．
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// zlib形式に用いるAdler-32を計算する．
+fn adler32(data: &[u8]) -> u32 {
+    const MODULO: u32 = 65521;
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + u32::from(byte)) % MODULO;
+        (a, (b + a) % MODULO)
+    });
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BorderStyle, UnitColor};
+    use data_structure::Pair;
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+        assert_eq!(0x11e6_0398, adler32(b"Wikipedia"));
+    }
+
+    #[test]
+    fn test_zlib_stored() {
+        let data = vec![7; MAX_STORED_BLOCK_LENGTH + 1];
+        let zlib = zlib_stored(&data);
+        // ヘッダ2バイト，ブロック2つ(各5バイトのヘッダ)，Adler-32の4バイト
+        assert_eq!(2 + 5 * 2 + data.len() + 4, zlib.len());
+        assert_eq!(0, zlib[2]);
+        assert_eq!(1, zlib[2 + 5 + MAX_STORED_BLOCK_LENGTH]);
+    }
+
+    #[test]
+    fn test_to_png() {
+        let mut canvas = Canvas::with_size(Pair::new(1, 1));
+        canvas.set_border_style(BorderStyle::None);
+        canvas.draw_unit(
+            DrawableUnit::from_double_half_char('_', ' ', UnitColor::Red)
+                .with_background(UnitColor::Blue),
+            CanvasItemPosition::new(0, 0),
+            0,
+        );
+        let png = canvas.to_png();
+        assert_eq!(&PNG_SIGNATURE[..], &png[..8]);
+        // IHDRチャンクに幅と高さが格納される
+        assert_eq!(b"IHDR", &png[12..16]);
+        assert_eq!(&16u32.to_be_bytes(), &png[16..20]);
+        assert_eq!(&16u32.to_be_bytes(), &png[20..24]);
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+    }
+
+    #[test]
+    fn test_draw_unit() {
+        let mut image = Image::new(CELL_WIDTH, CELL_HEIGHT);
+        image.draw_unit(
+            DrawableUnit::from_double_half_char('_', ' ', UnitColor::Red)
+                .with_background(UnitColor::Blue),
+            CanvasItemPosition::new(0, 0),
+        );
+        let pixel = |x: usize, y: usize| {
+            let index = (y * CELL_WIDTH + x) * 3;
+            (
+                image.pixels[index],
+                image.pixels[index + 1],
+                image.pixels[index + 2],
+            )
+        };
+        assert_eq!(UnitColor::Red.rgb(), pixel(0, CELL_HEIGHT - 1));
+        assert_eq!(UnitColor::Blue.rgb(), pixel(0, 0));
+        assert_eq!(
+            UnitColor::Blue.rgb(),
+            pixel(CELL_WIDTH - 1, CELL_HEIGHT - 1)
+        );
+    }
+
+    #[test]
+    fn test_draw_kana_unit() {
+        let render = |c: char| {
+            let mut image = Image::new(CELL_WIDTH, CELL_HEIGHT);
+            image.draw_unit(
+                DrawableUnit::from_single_full_char(c, UnitColor::White),
+                CanvasItemPosition::new(0, 0),
+            );
+            image.pixels
+        };
+        // 収録されていない文字は四角形として描画される
+        let missing = render('龍');
+        for c in "あアが学".chars() {
+            assert_ne!(missing, render(c), "{}", c);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use super::{
+    css_color, displayed_colors, write_escaped_char, CELL_HEIGHT, CELL_WIDTH, DEFAULT_BACKGROUND,
+};
+use crate::{
+    Canvas, CanvasItemPosition, DrawDestination, DrawError, DrawableUnit, Layer, UnitAttributes,
+};
+use std::fmt;
+
+/// 文字の大きさ(ピクセル)．
+const FONT_SIZE: usize = 14;
+/// 描画単位の上端から文字のベースラインまでの距離(ピクセル)．
+const BASELINE: usize = 13;
+
+impl<L: Layer> Canvas<L> {
+    /// このキャンバスの内容を，枠とともにSVG画像として書き込む．
+    /// 各描画単位は16x16ピクセルの格子に配置され，半角文字は幅8ピクセル，全角文字は幅16ピクセルの`text`要素となる．
+    pub fn write_svg_to<D: DrawDestination>(&self, destination: &mut D) -> Result<(), DrawError> {
+        let framed_size = self.framed_size();
+        let width = framed_size.x * CELL_WIDTH;
+        let height = framed_size.y * CELL_HEIGHT;
+        writeln!(
+            destination,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"monospace\" font-size=\"{2}\">",
+            width, height, FONT_SIZE
+        )?;
+        writeln!(
+            destination,
+            "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            width,
+            height,
+            css_color(DEFAULT_BACKGROUND)
+        )?;
+        for y in 0..framed_size.y {
+            for x in 0..framed_size.x {
+                let position = CanvasItemPosition::new(x, y);
+                write_unit_svg(self.framed_unit_at(position), position, destination)?;
+            }
+        }
+        destination.write_str("</svg>\n")?;
+        Ok(())
+    }
+
+    /// このキャンバスの内容を，枠とともにSVG画像として返す．
+    /// 書式は`write_svg_to`と同じである．
+    pub fn to_svg(&self) -> String {
+        let mut s = String::new();
+        self.write_svg_to(&mut s)
+            .expect("Writing to String never fails.");
+        s
+    }
+}
+
+/// 枠を含めた座標`position`にある描画単位を，背景の`rect`要素と文字の`text`要素として書き込む．
+fn write_unit_svg<D: DrawDestination>(
+    unit: DrawableUnit,
+    position: CanvasItemPosition,
+    destination: &mut D,
+) -> fmt::Result {
+    let left = position.x * CELL_WIDTH;
+    let top = position.y * CELL_HEIGHT;
+    let (foreground, background) = displayed_colors(&unit);
+    if let Some(background) = background {
+        writeln!(
+            destination,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            left,
+            top,
+            CELL_WIDTH,
+            CELL_HEIGHT,
+            css_color(background)
+        )?;
+    }
+
+    let chars = unit.chars().collect::<Vec<_>>();
+    let char_width = CELL_WIDTH / chars.len();
+    let attributes = unit.attributes();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == ' ' || c == '\u{3000}' {
+            continue;
+        }
+        write!(
+            destination,
+            "<text x=\"{}\" y=\"{}\" textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\" fill=\"{}\"",
+            left + i * char_width,
+            top + BASELINE,
+            char_width,
+            css_color(foreground)
+        )?;
+        if attributes.contains(UnitAttributes::BOLD) {
+            destination.write_str(" font-weight=\"bold\"")?;
+        }
+        if attributes.contains(UnitAttributes::DIM) {
+            destination.write_str(" opacity=\"0.5\"")?;
+        }
+        if attributes.contains(UnitAttributes::UNDERLINE) {
+            destination.write_str(" text-decoration=\"underline\"")?;
+        }
+        destination.write_char('>')?;
+        write_escaped_char(c, destination)?;
+        destination.write_str("</text>\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BorderStyle, UnitColor};
+    use data_structure::Pair;
+
+    #[test]
+    fn test_to_svg() {
+        let mut canvas = Canvas::with_size(Pair::new(2, 1));
+        canvas.set_border_style(BorderStyle::None);
+        let unit = DrawableUnit::from_double_half_char('a', '<', UnitColor::Red)
+            .with_background(UnitColor::Blue);
+        canvas.draw_unit(unit, CanvasItemPosition::new(0, 0), 0);
+        canvas.draw_unit(
+            DrawableUnit::from_single_full_char('あ', UnitColor::Green),
+            CanvasItemPosition::new(1, 0),
+            0,
+        );
+        let svg = canvas.to_svg();
+        assert!(
+            svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"32\" height=\"16\"")
+        );
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"16\" height=\"16\" fill=\"#0000ee\"/>"));
+        assert!(svg.contains(
+            "<text x=\"0\" y=\"13\" textLength=\"8\" lengthAdjust=\"spacingAndGlyphs\" fill=\"#cd0000\">a</text>"
+        ));
+        assert!(svg.contains(
+            "<text x=\"8\" y=\"13\" textLength=\"8\" lengthAdjust=\"spacingAndGlyphs\" fill=\"#cd0000\">&lt;</text>"
+        ));
+        assert!(svg.contains(
+            "<text x=\"16\" y=\"13\" textLength=\"16\" lengthAdjust=\"spacingAndGlyphs\" fill=\"#00cd00\">あ</text>"
+        ));
+    }
+
+    #[test]
+    fn spaces_are_not_written() {
+        let mut canvas = Canvas::<i32>::with_size(Pair::new(1, 1));
+        canvas.set_border_style(BorderStyle::None);
+        assert!(!canvas.to_svg().contains("<text"));
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{Canvas, ColorSupport, DiffRenderer, DrawError, Layer};
+use std::io;
+
+/// 描画したフレームを`io::Write`に書き込む．
+///
+/// 各フレームはいったん内部のバッファに書き込まれ，まとめて出力先に書き込まれた後，1度だけフラッシュされる．
+/// バッファはフレーム間で再利用される．
+#[derive(Debug)]
+pub struct FrameWriter<W: io::Write> {
+    /// 出力先．
+    writer: W,
+    /// フレームの内容を一時的に保持するバッファ．
+    buffer: String,
+    /// フレームの書き込みに用いる色表現範囲．
+    color_support: ColorSupport,
+}
+
+impl<W: io::Write> FrameWriter<W> {
+    /// 指定した出力先に，`ColorSupport::current`で表現可能な色でフレームを書き込むオブジェクトを返す．
+    pub fn new(writer: W) -> Self {
+        Self::with_color_support(writer, ColorSupport::current())
+    }
+
+    /// 指定した出力先に，指定した色表現範囲でフレームを書き込むオブジェクトを返す．
+    pub fn with_color_support(writer: W, color_support: ColorSupport) -> Self {
+        Self {
+            writer,
+            buffer: String::new(),
+            color_support,
+        }
+    }
+
+    /// 出力先への参照を返す．
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// 出力先への可変参照を返す．
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// このオブジェクトを破棄し，出力先を返す．
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// 最後に書き込んだフレームの内容を返す．
+    /// 描画に失敗した場合は，失敗するまでに描画された内容を返す．
+    pub fn last_frame(&self) -> &str {
+        &self.buffer
+    }
+
+    /// キャンバスの内容を，枠とともにすべて出力先に書き込む．
+    pub fn write_canvas<L: Layer>(&mut self, canvas: &Canvas<L>) -> Result<(), DrawError> {
+        let color_support = self.color_support;
+        self.write_frame(|buffer| canvas.write_with_color_support_to(buffer, color_support))
+    }
+
+    /// 差分レンダラを用いて，キャンバスの内容を出力先に書き込む．
+    /// 書き込みに失敗した場合，端末の表示内容は不明となるため，次回の描画で画面全体が書き直される．
+    pub fn render<L: Layer>(
+        &mut self,
+        renderer: &mut DiffRenderer,
+        canvas: &Canvas<L>,
+    ) -> Result<(), DrawError> {
+        let color_support = self.color_support;
+        let result = self.write_frame(|buffer| {
+            renderer.render_with_color_support(canvas, buffer, color_support)
+        });
+        if result.is_err() {
+            renderer.request_full_redraw();
+        }
+        result
+    }
+
+    /// 指定した関数でバッファに描画した内容を，1つのフレームとして出力先に書き込み，フラッシュする．
+    /// 描画に失敗した場合，出力先には何も書き込まれない．
+    pub fn write_frame<F>(&mut self, draw: F) -> Result<(), DrawError>
+    where
+        F: FnOnce(&mut String) -> Result<(), DrawError>,
+    {
+        self.buffer.clear();
+        draw(&mut self.buffer)?;
+        self.writer.write_all(self.buffer.as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorSupport, DrawableUnit, UnitColor};
+    use data_structure::Pair;
+
+    /// 書き込まれた内容とフラッシュの回数を記録する出力先．
+    /// `failing`が`true`の間は，書き込みに失敗する．
+    #[derive(Debug, Default)]
+    struct RecordingWriter {
+        written: Vec<u8>,
+        flush_count: usize,
+        failing: bool,
+    }
+
+    impl io::Write for RecordingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.failing {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "failing writer"));
+            }
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            self.flush_count += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn one_flush_per_frame() {
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        canvas.draw_unit(
+            DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red),
+            Pair::new(1, 1),
+            0,
+        );
+        let mut writer =
+            FrameWriter::with_color_support(RecordingWriter::default(), ColorSupport::Ansi16);
+        writer.write_canvas(&canvas).unwrap();
+        let mut expected = String::new();
+        canvas
+            .write_with_color_support_to(&mut expected, ColorSupport::Ansi16)
+            .unwrap();
+        assert_eq!(expected.as_bytes(), writer.get_ref().written.as_slice());
+        assert_eq!(1, writer.get_ref().flush_count);
+        assert_eq!(expected, writer.last_frame());
+
+        let mut renderer = DiffRenderer::new();
+        writer.render(&mut renderer, &canvas).unwrap();
+        writer.render(&mut renderer, &canvas).unwrap();
+        assert_eq!(3, writer.get_ref().flush_count);
+    }
+
+    #[test]
+    fn failed_frame_writes_nothing() {
+        let mut writer = FrameWriter::with_color_support(Vec::new(), ColorSupport::Ansi16);
+        let result = writer.write_frame(|buffer| {
+            DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red)
+                .write_with_color_support_to(buffer, ColorSupport::Ansi16)?;
+            Err(DrawError::Destination(std::fmt::Error))
+        });
+        assert!(result.is_err());
+        assert!(writer.into_inner().is_empty());
+    }
+
+    #[test]
+    fn failed_write_causes_full_redraw() {
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        let mut renderer = DiffRenderer::new();
+        let mut writer =
+            FrameWriter::with_color_support(RecordingWriter::default(), ColorSupport::Ansi16);
+        writer.render(&mut renderer, &canvas).unwrap();
+
+        canvas.draw_unit(
+            DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red),
+            Pair::new(1, 1),
+            0,
+        );
+        writer.get_mut().failing = true;
+        assert!(writer.render(&mut renderer, &canvas).is_err());
+
+        // 失敗したフレームの内容は端末に届いていないため，差分ではなく全体が書き直される
+        writer.get_mut().failing = false;
+        writer.get_mut().written.clear();
+        writer.render(&mut renderer, &canvas).unwrap();
+        let mut expected = String::from("\x1b[2J\x1b[1;1H");
+        canvas
+            .write_with_color_support_to(&mut expected, ColorSupport::Ansi16)
+            .unwrap();
+        assert_eq!(expected.as_bytes(), writer.get_ref().written.as_slice());
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{Layer, UnitColor};
+use std::collections::HashMap;
+
+/// アスキーアート中の文字に対応づける描画情報．
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegendEntry<L> {
+    /// 文字色．
+    pub color: Option<UnitColor>,
+    /// 背景色．
+    pub background: Option<UnitColor>,
+    /// レイヤー．
+    pub layer: Option<L>,
+}
+
+/// アスキーアート中の文字と，描画情報との対応を表す．
+/// 各描画単位は，その左側の文字 (全角文字の場合はその文字)によって対応づけられる．
+/// # Examples
+/// ```rust
+/// use cui_gaming::{Legend, UnitColor};
+///
+/// let legend = Legend::new()
+///     .with_color('#', UnitColor::White)
+///     .with_layer('#', 1)
+///     .with_color('~', UnitColor::Blue);
+/// assert_eq!(Some(1), legend.entry('#').and_then(|entry| entry.layer));
+/// assert_eq!(None, legend.entry('.'));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Legend<L> {
+    entries: HashMap<char, LegendEntry<L>>,
+}
+
+impl<L> LegendEntry<L> {
+    /// 何も指定しない対応を返す．
+    pub const fn empty() -> Self {
+        Self {
+            color: None,
+            background: None,
+            layer: None,
+        }
+    }
+}
+
+impl<L: Layer> Legend<L> {
+    /// 対応を1つも持たない凡例を返す．
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// 指定した文字に文字色を対応づけたものを返す．
+    pub fn with_color(mut self, c: char, color: UnitColor) -> Self {
+        self.entry_mut(c).color = Some(color);
+        self
+    }
+
+    /// 指定した文字に背景色を対応づけたものを返す．
+    pub fn with_background(mut self, c: char, background: UnitColor) -> Self {
+        self.entry_mut(c).background = Some(background);
+        self
+    }
+
+    /// 指定した文字にレイヤーを対応づけたものを返す．
+    pub fn with_layer(mut self, c: char, layer: L) -> Self {
+        self.entry_mut(c).layer = Some(layer);
+        self
+    }
+
+    /// 指定した文字に対応づけられた描画情報を返す．対応が存在しない場合は`None`を返す．
+    pub fn entry(&self, c: char) -> Option<&LegendEntry<L>> {
+        self.entries.get(&c)
+    }
+
+    fn entry_mut(&mut self, c: char) -> &mut LegendEntry<L> {
+        self.entries.entry(c).or_insert_with(LegendEntry::empty)
+    }
+}
+
+impl<L: Layer> Default for Legend<L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,15 +1,39 @@
+pub mod backend;
+pub mod border;
 pub mod canvas;
+pub mod cast_recorder;
+pub mod color;
 pub mod drawable_unit;
+pub mod export;
+pub mod frame_writer;
 pub mod input;
 pub mod layer;
+pub mod legend;
 pub mod message_buffer;
+pub mod renderer;
+pub mod snapshot;
+pub mod sprite;
+pub mod style;
+pub mod terminal;
+pub mod testing;
 pub mod ui_canvas;
 pub mod world_canvas;
 
+pub use backend::*;
+pub use border::*;
 pub use canvas::*;
+pub use cast_recorder::*;
+pub use color::*;
 pub use drawable_unit::*;
+pub use frame_writer::*;
 pub use input::*;
 pub use layer::*;
+pub use legend::*;
 pub use message_buffer::*;
+pub use renderer::*;
+pub use snapshot::*;
+pub use sprite::*;
+pub use style::*;
+pub use terminal::*;
 pub use ui_canvas::*;
 pub use world_canvas::*;
@@ -300,6 +300,35 @@ mod message_line_tests {
     }
 }
 
+#[cfg(test)]
+mod message_buffer_tests {
+    use super::*;
+    use crate::Canvas;
+    use data_structure::Pair;
+    #[test]
+    fn test_draw_message_larger_than_canvas() {
+        let border_unit = DrawableUnit::from_double_half_char('#', '#', UnitColor::White);
+        let mut buffer = MessageBuffer::new(3, 10, border_unit);
+        buffer.add_text("abcd", UnitColor::White);
+        let mut canvas = Canvas::with_size(Pair::new(4, 3));
+        let mut ui_canvas = UiCanvas::from_canvas(&mut canvas);
+        // キャンバスからはみ出す領域を指定しても，キャンバス内の部分だけが描画される
+        let region = Rectangle::from_corners(UiPosition::new(1, 0), UiPosition::new(6, 3));
+        buffer.draw_message(&mut ui_canvas, region, 0);
+        assert_eq!(Some(border_unit), canvas.unit_at(Pair::new(1, 0)));
+        assert_eq!(Some(border_unit), canvas.unit_at(Pair::new(1, 2)));
+        assert_eq!(None, canvas.unit_at(Pair::new(0, 1)));
+        assert_eq!(
+            Some(DrawableUnit::from_double_half_char(
+                'a',
+                'b',
+                UnitColor::White
+            )),
+            canvas.unit_at(Pair::new(2, 2))
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::div_ceil;
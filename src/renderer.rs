@@ -0,0 +1,313 @@
+use crate::{
+    BorderStyle, Canvas, CanvasItemPosition, CanvasLattice, ColorSupport, DrawDestination,
+    DrawError, DrawableUnit, Layer, TerminalLattice,
+};
+use data_structure::Pair;
+
+/// 直前に端末へ出力したフレームの内容．
+#[derive(Debug, Clone)]
+struct Frame {
+    /// フレームのサイズ．
+    size: Pair<CanvasLattice>,
+    /// フレームの枠の描画方法．描画単位の端末上の位置もこれによって決まる．
+    border_style: BorderStyle,
+    /// フレーム内の各点に表示されている描画単位．行優先で格納される．
+    units: Vec<DrawableUnit>,
+}
+
+/// キャンバスの内容を，前回出力したフレームとの差分のみ端末に書き込む．
+///
+/// 差分の書き込みにはカーソル位置指定のエスケープシーケンスを用いるため，
+/// 端末の画面全体がこのオブジェクトによって管理されていることを前提とする．
+#[derive(Debug, Clone)]
+pub struct DiffRenderer {
+    /// 前回出力したフレーム (フロントバッファ)．まだ何も出力していない場合は`None`．
+    front: Option<Frame>,
+    /// 次回の描画で画面全体を書き直すか．
+    full_redraw_requested: bool,
+    /// `render`で用いる色表現範囲．
+    color_support: ColorSupport,
+}
+
+impl DiffRenderer {
+    /// まだ何も出力していない状態の，`ColorSupport::current`で表現可能な色で描画するレンダラを返す．
+    /// 最初の描画では，画面全体が書き込まれる．
+    pub fn new() -> Self {
+        Self::with_color_support(ColorSupport::current())
+    }
+
+    /// まだ何も出力していない状態の，指定した色表現範囲で描画するレンダラを返す．
+    pub fn with_color_support(color_support: ColorSupport) -> Self {
+        Self {
+            front: None,
+            full_redraw_requested: false,
+            color_support,
+        }
+    }
+
+    /// 次回の描画で，差分ではなく画面全体を書き直すよう要求する．
+    /// 端末の内容が外部から書き換えられた可能性がある場合に用いる．
+    pub fn request_full_redraw(&mut self) {
+        self.full_redraw_requested = true;
+    }
+
+    /// キャンバスの内容を描画先に書き込む．
+    /// 初回の描画，キャンバスのサイズまたは枠の描画方法の変更後，または`request_full_redraw`の呼び出し後は，画面を消去してキャンバス全体を書き込む．
+    /// その他の場合は，前回の描画から変化した点のみを書き込む．
+    ///
+    /// 色は，生成時に指定した色表現範囲に変換される．
+    pub fn render<L: Layer, D: DrawDestination>(
+        &mut self,
+        canvas: &Canvas<L>,
+        destination: &mut D,
+    ) -> Result<(), DrawError> {
+        let color_support = self.color_support;
+        self.render_with_color_support(canvas, destination, color_support)
+    }
+
+    /// キャンバスの内容を，色を指定した色表現範囲に変換したうえで描画先に書き込む．
+    /// 書き込む範囲は`render`と同じである．
+    pub fn render_with_color_support<L: Layer, D: DrawDestination>(
+        &mut self,
+        canvas: &Canvas<L>,
+        destination: &mut D,
+        color_support: ColorSupport,
+    ) -> Result<(), DrawError> {
+        let back = Frame::from_canvas(canvas);
+        let needs_full_redraw = self.full_redraw_requested
+            || match &self.front {
+                Some(front) => front.size != back.size || front.border_style != back.border_style,
+                None => true,
+            };
+        match &self.front {
+            Some(front) if !needs_full_redraw => Self::write_difference(
+                front,
+                &back,
+                canvas.content_origin(),
+                destination,
+                color_support,
+            )?,
+            _ => {
+                // 画面を消去し，カーソルを左上に移動してから全体を書き込む
+                destination.write_str("\x1b[2J")?;
+                write_cursor_position(Pair::new(0, 0), destination)?;
+                canvas.write_with_color_support_to(destination, color_support)?;
+            }
+        }
+        // 書き込みに成功した場合のみ，フロントバッファを更新する
+        self.front = Some(back);
+        self.full_redraw_requested = false;
+        Ok(())
+    }
+
+    /// 2つのフレーム間で変化した点のみを書き込む．
+    fn write_difference<D: DrawDestination>(
+        front: &Frame,
+        back: &Frame,
+        content_origin: Pair<TerminalLattice>,
+        destination: &mut D,
+        color_support: ColorSupport,
+    ) -> Result<(), DrawError> {
+        // 現在のカーソル位置が既知であり，かつ次に書き込む点と一致する場合はカーソル移動を省略する
+        let mut cursor = None;
+        for (index, (front_unit, back_unit)) in
+            front.units.iter().zip(back.units.iter()).enumerate()
+        {
+            if front_unit == back_unit {
+                continue;
+            }
+            let position = CanvasItemPosition::new(index % back.size.x, index / back.size.x);
+            let terminal_position = Pair::new(
+                content_origin.x + position.x * 2,
+                content_origin.y + position.y,
+            );
+            if cursor != Some(terminal_position) {
+                write_cursor_position(terminal_position, destination)?;
+            }
+            back_unit.write_with_color_support_to(destination, color_support)?;
+            // 描画単位は必ず2列を占有する
+            cursor = Some(Pair::new(terminal_position.x + 2, terminal_position.y));
+        }
+        Ok(())
+    }
+}
+
+impl Default for DiffRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Frame {
+    /// キャンバスに現在描画されている内容からフレームを生成する．
+    fn from_canvas<L: Layer>(canvas: &Canvas<L>) -> Self {
+        let size = canvas.size();
+        let units = (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| CanvasItemPosition::new(x, y)))
+            .map(|position| canvas.displayed_unit_at(position))
+            .collect();
+        Self {
+            size,
+            border_style: canvas.border_style(),
+            units,
+        }
+    }
+}
+
+/// カーソルを端末上の指定した位置 (0始まり，文字セル単位)に移動するエスケープシーケンスを書き込む．
+fn write_cursor_position<D: DrawDestination>(
+    position: Pair<TerminalLattice>,
+    destination: &mut D,
+) -> Result<(), DrawError> {
+    destination.write_fmt(format_args!("\x1b[{};{}H", position.y + 1, position.x + 1))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UnitColor;
+
+    /// テストの結果が環境に依存しないよう，すべての書き込みで用いる色表現範囲．
+    const COLOR_SUPPORT: ColorSupport = ColorSupport::Ansi16;
+
+    fn unit_string(unit: DrawableUnit) -> String {
+        let mut s = String::new();
+        unit.write_with_color_support_to(&mut s, COLOR_SUPPORT)
+            .unwrap();
+        s
+    }
+
+    #[test]
+    fn first_render_is_full_redraw() {
+        let canvas = Canvas::<i32>::with_size(Pair::new(3, 2));
+        let mut full = String::new();
+        canvas
+            .write_with_color_support_to(&mut full, COLOR_SUPPORT)
+            .unwrap();
+        let mut output = String::new();
+        DiffRenderer::new()
+            .render_with_color_support(&canvas, &mut output, COLOR_SUPPORT)
+            .unwrap();
+        assert_eq!(format!("\x1b[2J\x1b[1;1H{}", full), output);
+    }
+
+    #[test]
+    fn unchanged_canvas_writes_nothing() {
+        let canvas = Canvas::<i32>::with_size(Pair::new(3, 2));
+        let mut renderer = DiffRenderer::new();
+        renderer
+            .render_with_color_support(&canvas, &mut String::new(), COLOR_SUPPORT)
+            .unwrap();
+        let mut output = String::new();
+        renderer
+            .render_with_color_support(&canvas, &mut output, COLOR_SUPPORT)
+            .unwrap();
+        assert_eq!("", output);
+    }
+
+    #[test]
+    fn only_changed_units_are_written() {
+        let mut canvas = Canvas::with_size(Pair::new(4, 3));
+        let mut renderer = DiffRenderer::new();
+        renderer
+            .render_with_color_support(&canvas, &mut String::new(), COLOR_SUPPORT)
+            .unwrap();
+        let a = DrawableUnit::from_double_half_char('a', 'a', UnitColor::Red);
+        let b = DrawableUnit::from_double_half_char('b', 'b', UnitColor::Red);
+        let c = DrawableUnit::from_double_half_char('c', 'c', UnitColor::Red);
+        canvas.draw_unit(a, CanvasItemPosition::new(1, 1), 0);
+        canvas.draw_unit(b, CanvasItemPosition::new(2, 1), 0);
+        canvas.draw_unit(c, CanvasItemPosition::new(0, 2), 0);
+        let mut output = String::new();
+        renderer
+            .render_with_color_support(&canvas, &mut output, COLOR_SUPPORT)
+            .unwrap();
+        // 隣接する点はカーソル移動なしで続けて書き込まれる
+        let expected = format!(
+            "\x1b[3;5H{}{}\x1b[4;3H{}",
+            unit_string(a),
+            unit_string(b),
+            unit_string(c)
+        );
+        assert_eq!(expected, output);
+    }
+
+    #[test]
+    fn resize_and_request_cause_full_redraw() {
+        let mut canvas = Canvas::<i32>::with_size(Pair::new(3, 2));
+        let mut renderer = DiffRenderer::new();
+        renderer
+            .render_with_color_support(&canvas, &mut String::new(), COLOR_SUPPORT)
+            .unwrap();
+        renderer.request_full_redraw();
+        let mut output = String::new();
+        renderer
+            .render_with_color_support(&canvas, &mut output, COLOR_SUPPORT)
+            .unwrap();
+        assert!(output.starts_with("\x1b[2J"));
+        canvas.resize(Pair::new(4, 2));
+        let mut output = String::new();
+        renderer
+            .render_with_color_support(&canvas, &mut output, COLOR_SUPPORT)
+            .unwrap();
+        assert!(output.starts_with("\x1b[2J"));
+        // 全体の書き直し後は，再び差分のみが書き込まれる
+        let mut output = String::new();
+        renderer
+            .render_with_color_support(&canvas, &mut output, COLOR_SUPPORT)
+            .unwrap();
+        assert_eq!("", output);
+    }
+
+    #[test]
+    fn border_style_change_causes_full_redraw() {
+        let mut canvas = Canvas::<i32>::with_size(Pair::new(3, 2));
+        let mut renderer = DiffRenderer::new();
+        renderer
+            .render_with_color_support(&canvas, &mut String::new(), COLOR_SUPPORT)
+            .unwrap();
+        canvas.set_border_style(BorderStyle::None);
+        let mut output = String::new();
+        renderer
+            .render_with_color_support(&canvas, &mut output, COLOR_SUPPORT)
+            .unwrap();
+        let mut full = String::new();
+        canvas
+            .write_with_color_support_to(&mut full, COLOR_SUPPORT)
+            .unwrap();
+        assert_eq!(format!("\x1b[2J\x1b[1;1H{}", full), output);
+        // 枠の色のみの変更でも全体が書き直される
+        canvas.set_border_style(BorderStyle::Single(UnitColor::Red));
+        renderer
+            .render_with_color_support(&canvas, &mut String::new(), COLOR_SUPPORT)
+            .unwrap();
+        canvas.set_border_style(BorderStyle::Single(UnitColor::Blue));
+        let mut output = String::new();
+        renderer
+            .render_with_color_support(&canvas, &mut output, COLOR_SUPPORT)
+            .unwrap();
+        assert!(output.starts_with("\x1b[2J"));
+    }
+
+    #[test]
+    fn render_uses_color_support_given_at_construction() {
+        let mut canvas = Canvas::with_size(Pair::new(1, 1));
+        canvas.set_border_style(BorderStyle::None);
+        canvas.draw_unit(
+            DrawableUnit::from_double_half_char('a', 'b', UnitColor::Rgb(255, 0, 0)),
+            CanvasItemPosition::new(0, 0),
+            0,
+        );
+        let mut expected = String::new();
+        DiffRenderer::new()
+            .render_with_color_support(&canvas, &mut expected, ColorSupport::Monochrome)
+            .unwrap();
+        let mut output = String::new();
+        DiffRenderer::with_color_support(ColorSupport::Monochrome)
+            .render(&canvas, &mut output)
+            .unwrap();
+        assert_eq!(expected, output);
+        assert!(!output.contains(";38;2;"));
+    }
+}
@@ -0,0 +1,509 @@
+//! キャンバスの内容をテキストとして保存し，読み込む機能を提供する．
+//!
+//! # 形式
+//! スナップショットはUTF-8のテキストであり，1行に1つの項目を記述する．
+//! 空行および`#`で始まる行は無視される．
+//!
+//! ```text
+//! cui_gaming-canvas 1
+//! size 3 2
+//! cell 0 0 5 "ab" red - bold,underline
+//! cell 2 1 0 "あ" #ff8000 bright-blue -
+//! ```
+//!
+//! 1. 1行目は`cui_gaming-canvas`とバージョン番号(現在は`1`)である．
+//! 1. 2行目は`size`とキャンバスの幅および高さである．幅と高さの積は`MAX_SNAPSHOT_CELLS`以下でなければならない．
+//! 1. 以降の各行は，何かが描画されている点1つを表し，以下の項目を空白で区切って記述する．
+//!    1. `cell`
+//!    1. 点のx座標およびy座標
+//!    1. レイヤー．レイヤーの型の`Display`による表現であり，空白を含んではならない．
+//!    1. 文字．半角文字2つまたは全角文字1つを`"`で囲んだもの．`"`および`\`は`\`でエスケープする．
+//!    1. 文字色
+//!    1. 背景色．端末の既定の背景色の場合は`-`．
+//!    1. 文字装飾．`bold`，`dim`，`underline`，`reverse`，`blink`を`,`で区切ったもの．装飾がない場合は`-`．
+//!
+//! 色は`black`，`red`，`green`，`yellow`，`blue`，`magenta`，`cyan`，`white`およびこれらに`bright-`を付けたもの，
+//! xterm 256色パレットの色を表す`ansi256:番号`，24bit RGB色を表す`#rrggbb`のいずれかである．
+//!
+//! `Canvas::save`は点を行優先で書き出すため，同じ内容のキャンバスからは常に同じスナップショットが得られる．
+//! 枠のスタイルおよびサイズの決定方法は保存されない．
+
+use crate::{
+    Canvas, CanvasItemPosition, CanvasLattice, DrawableUnit, Layer, UnitAttributes, UnitColor,
+};
+use data_structure::Pair;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use unicode_width::UnicodeWidthChar;
+
+/// スナップショットの1行目に記述される，形式の名前．
+const SNAPSHOT_FORMAT_NAME: &str = "cui_gaming-canvas";
+/// このクレートが読み書きするスナップショット形式のバージョン．
+pub const SNAPSHOT_VERSION: u32 = 1;
+/// `Canvas::load`が読み込むキャンバスの点の数(幅と高さの積)の上限．
+pub const MAX_SNAPSHOT_CELLS: usize = 1 << 24;
+
+/// 基本16色と，スナップショット中での名前の対応．
+const COLOR_NAMES: [(UnitColor, &str); 16] = [
+    (UnitColor::Black, "black"),
+    (UnitColor::Red, "red"),
+    (UnitColor::Green, "green"),
+    (UnitColor::Yellow, "yellow"),
+    (UnitColor::Blue, "blue"),
+    (UnitColor::Magenta, "magenta"),
+    (UnitColor::Cyan, "cyan"),
+    (UnitColor::White, "white"),
+    (UnitColor::BrightBlack, "bright-black"),
+    (UnitColor::BrightRed, "bright-red"),
+    (UnitColor::BrightGreen, "bright-green"),
+    (UnitColor::BrightYellow, "bright-yellow"),
+    (UnitColor::BrightBlue, "bright-blue"),
+    (UnitColor::BrightMagenta, "bright-magenta"),
+    (UnitColor::BrightCyan, "bright-cyan"),
+    (UnitColor::BrightWhite, "bright-white"),
+];
+
+/// 文字装飾と，スナップショット中での名前の対応．
+const ATTRIBUTE_NAMES: [(UnitAttributes, &str); 5] = [
+    (UnitAttributes::BOLD, "bold"),
+    (UnitAttributes::DIM, "dim"),
+    (UnitAttributes::UNDERLINE, "underline"),
+    (UnitAttributes::REVERSE, "reverse"),
+    (UnitAttributes::BLINK, "blink"),
+];
+
+/// スナップショットの読み込み時のエラーを表す型．
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// 読み込み元からの読み込みに失敗した．
+    Io(io::Error),
+    /// 読み込み元がUTF-8のテキストでない．
+    InvalidUtf8,
+    /// このクレートが対応していないバージョンの形式である．
+    UnsupportedVersion(u32),
+    /// 記述が形式に従っていない．
+    Syntax {
+        /// 誤りのある行番号(1始まり)．
+        line: usize,
+        /// 誤りの内容．
+        message: String,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "failed to read snapshot: {}", e),
+            SnapshotError::InvalidUtf8 => write!(f, "snapshot is not valid UTF-8"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+impl<L: Layer + fmt::Display> Canvas<L> {
+    /// このキャンバスの内容をスナップショット形式で書き込む．
+    /// 形式については`snapshot`モジュールの説明を参照．
+    pub fn save<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        let size = self.size();
+        writeln!(writer, "{} {}", SNAPSHOT_FORMAT_NAME, SNAPSHOT_VERSION)?;
+        writeln!(writer, "size {} {}", size.x, size.y)?;
+        for (position, unit, layer) in self.units() {
+            let mut glyph = String::new();
+            for c in unit.chars() {
+                if c == '"' || c == '\\' {
+                    glyph.push('\\');
+                }
+                glyph.push(c);
+            }
+            writeln!(
+                writer,
+                "cell {} {} {} \"{}\" {} {} {}",
+                position.x,
+                position.y,
+                layer,
+                glyph,
+                color_name(unit.color()),
+                unit.background().map_or_else(|| "-".to_owned(), color_name),
+                attributes_name(unit.attributes())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<L: Layer + FromStr> Canvas<L> {
+    /// スナップショット形式で記述されたキャンバスを読み込む．
+    /// 形式については`snapshot`モジュールの説明を参照．
+    /// # Returns
+    /// 読み込みに失敗した場合や，記述が形式に従っていない場合はエラーを返す．
+    pub fn load<R: io::Read>(reader: &mut R) -> Result<Self, SnapshotError> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        let text = String::from_utf8(bytes).map_err(|_| SnapshotError::InvalidUtf8)?;
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let (line_number, header) = lines
+            .next()
+            .ok_or_else(|| syntax_error(1, "missing header"))?;
+        match tokenize(header, line_number)?.as_slice() {
+            [name, version] if name == SNAPSHOT_FORMAT_NAME => {
+                let version = parse_token::<u32>(version, "version", line_number)?;
+                if version != SNAPSHOT_VERSION {
+                    return Err(SnapshotError::UnsupportedVersion(version));
+                }
+            }
+            _ => return Err(syntax_error(line_number, "invalid header")),
+        }
+
+        let (line_number, size_line) = lines
+            .next()
+            .ok_or_else(|| syntax_error(line_number + 1, "missing size"))?;
+        let size = match tokenize(size_line, line_number)?.as_slice() {
+            [keyword, width, height] if keyword == "size" => Pair::new(
+                parse_token::<CanvasLattice>(width, "width", line_number)?,
+                parse_token::<CanvasLattice>(height, "height", line_number)?,
+            ),
+            _ => return Err(syntax_error(line_number, "invalid size")),
+        };
+        // 確保できないサイズや上限を超えるサイズは，キャンバスを確保する前に弾く
+        match size.x.checked_mul(size.y) {
+            Some(cell_count)
+                if cell_count <= MAX_SNAPSHOT_CELLS
+                    && Canvas::<L>::storage_bytes(cell_count).is_some() => {}
+            _ => return Err(syntax_error(line_number, "size is too large")),
+        }
+
+        // 各点がサイズに収まることを確かめてから，キャンバスを確保する
+        let mut cells = vec![];
+        for (line_number, line) in lines {
+            let (position, unit, layer) = parse_cell::<L>(line, line_number)?;
+            if position.x >= size.x || position.y >= size.y {
+                return Err(syntax_error(line_number, "cell is out of the canvas"));
+            }
+            cells.push((line_number, position, unit, layer));
+        }
+        let mut canvas = Canvas::with_size(size);
+        for (line_number, position, unit, layer) in cells {
+            if canvas.layer_at(position).is_some() {
+                return Err(syntax_error(line_number, "duplicate cell"));
+            }
+            canvas.draw_unit(unit, position, layer);
+        }
+        Ok(canvas)
+    }
+}
+
+/// `cell`行を解釈し，点の位置，描画単位およびレイヤーを返す．
+fn parse_cell<L: FromStr>(
+    line: &str,
+    line_number: usize,
+) -> Result<(CanvasItemPosition, DrawableUnit, L), SnapshotError> {
+    let tokens = tokenize(line, line_number)?;
+    let (x, y, layer, glyph, color, background, attributes) = match tokens.as_slice() {
+        [keyword, x, y, layer, glyph, color, background, attributes] if keyword == "cell" => {
+            (x, y, layer, glyph, color, background, attributes)
+        }
+        _ => return Err(syntax_error(line_number, "invalid cell")),
+    };
+    let position = CanvasItemPosition::new(
+        parse_token(x, "x", line_number)?,
+        parse_token(y, "y", line_number)?,
+    );
+    let layer = parse_token::<L>(layer, "layer", line_number)?;
+    let color = parse_color(color).ok_or_else(|| syntax_error(line_number, "invalid color"))?;
+    let background = match background.as_str() {
+        "-" => None,
+        s => Some(parse_color(s).ok_or_else(|| syntax_error(line_number, "invalid background"))?),
+    };
+    let attributes = parse_attributes(attributes)
+        .ok_or_else(|| syntax_error(line_number, "invalid attributes"))?;
+
+    let glyph = glyph
+        .strip_prefix('"')
+        .ok_or_else(|| syntax_error(line_number, "glyph must be quoted"))?;
+    let chars = glyph.chars().collect::<Vec<_>>();
+    let unit = match chars.as_slice() {
+        [c] if c.width() == Some(2) => DrawableUnit::from_single_full_char(*c, color),
+        [left, right] if left.width() == Some(1) && right.width() == Some(1) => {
+            DrawableUnit::from_double_half_char(*left, *right, color)
+        }
+        _ => {
+            return Err(syntax_error(
+                line_number,
+                "glyph must be two half-width chars or one full-width char",
+            ))
+        }
+    };
+    let unit = match background {
+        Some(background) => unit.with_background(background),
+        None => unit,
+    };
+    Ok((position, unit.with_attributes(attributes), layer))
+}
+
+/// 行を空白で区切った項目の列に分割する．
+/// `"`で囲まれた項目は，エスケープを解除した内容の先頭に`"`を付けて返す．
+fn tokenize(line: &str, line_number: usize) -> Result<Vec<String>, SnapshotError> {
+    let mut tokens = vec![];
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut token = String::new();
+        if c == '"' {
+            token.push(chars.next().expect("Peeked."));
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(escaped) => token.push(escaped),
+                        None => return Err(syntax_error(line_number, "unterminated escape")),
+                    },
+                    Some(c) => token.push(c),
+                    None => return Err(syntax_error(line_number, "unterminated quote")),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+        }
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// 項目を指定した型の値として解釈する．
+fn parse_token<T: FromStr>(
+    token: &str,
+    name: &str,
+    line_number: usize,
+) -> Result<T, SnapshotError> {
+    token
+        .parse()
+        .map_err(|_| syntax_error(line_number, &format!("invalid {}", name)))
+}
+
+fn syntax_error(line: usize, message: &str) -> SnapshotError {
+    SnapshotError::Syntax {
+        line,
+        message: message.to_owned(),
+    }
+}
+
+/// 色をスナップショット中での表現に変換する．
+fn color_name(color: UnitColor) -> String {
+    match color {
+        UnitColor::Ansi256(index) => format!("ansi256:{}", index),
+        UnitColor::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        color => COLOR_NAMES
+            .iter()
+            .find(|(c, _)| *c == color)
+            .map(|(_, name)| (*name).to_owned())
+            .expect("All basic colors are named."),
+    }
+}
+
+/// スナップショット中での表現から色を求める．
+fn parse_color(s: &str) -> Option<UnitColor> {
+    if let Some(index) = s.strip_prefix("ansi256:") {
+        return index.parse().ok().map(UnitColor::Ansi256);
+    }
+    if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(UnitColor::Rgb(component(0)?, component(2)?, component(4)?));
+    }
+    COLOR_NAMES
+        .iter()
+        .find(|(_, name)| *name == s)
+        .map(|(color, _)| *color)
+}
+
+/// 文字装飾をスナップショット中での表現に変換する．
+fn attributes_name(attributes: UnitAttributes) -> String {
+    if attributes.is_empty() {
+        return "-".to_owned();
+    }
+    ATTRIBUTE_NAMES
+        .iter()
+        .filter(|(attribute, _)| attributes.contains(*attribute))
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// スナップショット中での表現から文字装飾を求める．
+fn parse_attributes(s: &str) -> Option<UnitAttributes> {
+    if s == "-" {
+        return Some(UnitAttributes::NONE);
+    }
+    s.split(',')
+        .try_fold(UnitAttributes::NONE, |attributes, name| {
+            ATTRIBUTE_NAMES
+                .iter()
+                .find(|(_, n)| *n == name)
+                .map(|(attribute, _)| attributes | *attribute)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_canvas() -> Canvas<i32> {
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        canvas.draw_unit(
+            DrawableUnit::from_double_half_char('"', '\\', UnitColor::Red)
+                .with_attributes(UnitAttributes::BOLD | UnitAttributes::UNDERLINE),
+            CanvasItemPosition::new(0, 0),
+            5,
+        );
+        canvas.draw_unit(
+            DrawableUnit::from_single_full_char('あ', UnitColor::Rgb(255, 128, 0))
+                .with_background(UnitColor::BrightBlue),
+            CanvasItemPosition::new(2, 1),
+            -1,
+        );
+        canvas.draw_unit(
+            DrawableUnit::from_double_half_char(' ', 'x', UnitColor::Ansi256(200)),
+            CanvasItemPosition::new(1, 1),
+            0,
+        );
+        canvas
+    }
+
+    #[test]
+    fn test_save() {
+        let mut bytes = vec![];
+        sample_canvas().save(&mut bytes).unwrap();
+        assert_eq!(
+            "cui_gaming-canvas 1\n\
+             size 3 2\n\
+             cell 0 0 5 \"\\\"\\\\\" red - bold,underline\n\
+             cell 1 1 0 \" x\" ansi256:200 - -\n\
+             cell 2 1 -1 \"あ\" #ff8000 bright-blue -\n",
+            String::from_utf8(bytes).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let canvas = sample_canvas();
+        let mut bytes = vec![];
+        canvas.save(&mut bytes).unwrap();
+        let loaded = Canvas::<i32>::load(&mut bytes.as_slice()).unwrap();
+        assert_eq!(canvas.size(), loaded.size());
+        assert_eq!(
+            canvas.units().collect::<Vec<_>>(),
+            loaded.units().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn load_ignores_comments_and_blank_lines() {
+        let snapshot = "# title screen\ncui_gaming-canvas 1\n\nsize 1 1\n# player\ncell 0 0 1 \"@ \" white - -\n";
+        let canvas = Canvas::<i32>::load(&mut snapshot.as_bytes()).unwrap();
+        assert_eq!(
+            Some(DrawableUnit::from_double_half_char(
+                '@',
+                ' ',
+                UnitColor::White
+            )),
+            canvas.unit_at(CanvasItemPosition::new(0, 0))
+        );
+        assert_eq!(Some(1), canvas.layer_at(CanvasItemPosition::new(0, 0)));
+    }
+
+    #[test]
+    fn load_errors() {
+        let load = |s: &str| Canvas::<i32>::load(&mut s.as_bytes());
+        match load("cui_gaming-canvas 2\nsize 1 1\n") {
+            Err(SnapshotError::UnsupportedVersion(2)) => {}
+            other => panic!("unexpected result: {:?}", other.map(|c| c.size())),
+        }
+        let syntax_error_line = |s: &str| match load(s) {
+            Err(SnapshotError::Syntax { line, .. }) => line,
+            other => panic!("unexpected result: {:?}", other.map(|c| c.size())),
+        };
+        assert_eq!(1, syntax_error_line("canvas 1\n"));
+        assert_eq!(2, syntax_error_line("cui_gaming-canvas 1\nsize 1\n"));
+        // 点の数が桁あふれするサイズ
+        assert_eq!(
+            2,
+            syntax_error_line("cui_gaming-canvas 1\nsize 18446744073709551615 2\n")
+        );
+        assert_eq!(
+            2,
+            syntax_error_line(
+                "cui_gaming-canvas 1\nsize 4294967296 4294967296\ncell 0 0 0 \"ab\" red - -\n"
+            )
+        );
+        // 桁あふれしないが確保できない，または上限を超えるサイズ
+        assert_eq!(
+            2,
+            syntax_error_line("cui_gaming-canvas 1\nsize 4294967295 4294967295\n")
+        );
+        assert_eq!(
+            2,
+            syntax_error_line("cui_gaming-canvas 1\nsize 100000 100000\n")
+        );
+        // キャンバス外の点
+        assert_eq!(
+            3,
+            syntax_error_line("cui_gaming-canvas 1\nsize 1 1\ncell 1 0 0 \"ab\" red - -\n")
+        );
+        // 正方形に収まらない文字
+        assert_eq!(
+            3,
+            syntax_error_line("cui_gaming-canvas 1\nsize 1 1\ncell 0 0 0 \"a\" red - -\n")
+        );
+        // 未知の色と装飾
+        assert_eq!(
+            3,
+            syntax_error_line("cui_gaming-canvas 1\nsize 1 1\ncell 0 0 0 \"ab\" pink - -\n")
+        );
+        assert_eq!(
+            3,
+            syntax_error_line("cui_gaming-canvas 1\nsize 1 1\ncell 0 0 0 \"ab\" red - italic\n")
+        );
+        // 同じ点の重複
+        assert_eq!(
+            4,
+            syntax_error_line(
+                "cui_gaming-canvas 1\nsize 1 1\ncell 0 0 0 \"ab\" red - -\ncell 0 0 1 \"cd\" red - -\n"
+            )
+        );
+    }
+}
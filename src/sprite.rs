@@ -0,0 +1,270 @@
+use crate::{Canvas, CanvasItemPosition, CanvasLattice, DrawableUnit, Layer, Legend, UnitColor};
+use data_structure::Pair;
+
+/// 複数の描画単位からなる，矩形の絵を表す．
+/// 何も描画されない(透明な)点を含むことができ，`Canvas::draw_sprite`で任意の位置に描画できる．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sprite<L> {
+    /// スプライトのサイズ．
+    size: Pair<CanvasLattice>,
+    /// 各点の描画単位とレイヤー．行優先で格納される．`None`は透明な点を表す．
+    cells: Vec<Option<(DrawableUnit, L)>>,
+}
+
+impl<L: Layer> Sprite<L> {
+    /// アスキーアートからスプライトを生成する．
+    /// 各行の文字は`DrawableUnit::create_units_from`と同じ規則で描画単位に変換され，
+    /// 半角空白2つからなる描画単位は透明な点となる．
+    /// 文字色，背景色およびレイヤーは，各描画単位の左側の文字 (全角文字の場合はその文字)に対応する`legend`の描画情報に従う．
+    /// 描画情報が指定されていない場合，文字色は白，背景色は端末の既定の色，レイヤーは`L::default()`となる．
+    ///
+    /// 先頭の空行および末尾の空白のみの行は取り除かれる．スプライトの幅は，最も長い行の描画単位の数となる．
+    /// # Panics on Debug Build
+    /// コンソールへの描画時に幅が1か2以外の文字が含まれる場合
+    /// # Examples
+    /// ```rust
+    /// use cui_gaming::{CanvasItemPosition, Legend, Sprite, UnitColor};
+    ///
+    /// let legend = Legend::new().with_color('o', UnitColor::Yellow).with_layer('o', 2);
+    /// let sprite = Sprite::from_ascii_art("  oo\noooo", &legend);
+    /// assert_eq!(2, sprite.size().x);
+    /// assert_eq!(None, sprite.unit_at(CanvasItemPosition::new(0, 0)));
+    /// assert_eq!(Some(2), sprite.layer_at(CanvasItemPosition::new(1, 1)));
+    /// ```
+    pub fn from_ascii_art(art: &str, legend: &Legend<L>) -> Self
+    where
+        L: Default,
+    {
+        let rows = ascii_art_rows(art)
+            .map(|line| {
+                DrawableUnit::create_units_from(line, UnitColor::White)
+                    .into_iter()
+                    .map(|unit| apply_legend(unit, legend))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let size = Pair::new(width, rows.len());
+        let cells = rows
+            .into_iter()
+            .flat_map(|mut row| {
+                row.resize(width, None);
+                row
+            })
+            .collect();
+        Self { size, cells }
+    }
+
+    /// このスプライトのサイズを返す．
+    pub const fn size(&self) -> Pair<CanvasLattice> {
+        self.size
+    }
+
+    /// 指定した点の描画単位を返す．透明な点，およびスプライト外の点については`None`を返す．
+    pub fn unit_at(&self, position: CanvasItemPosition) -> Option<DrawableUnit> {
+        self.cell_at(position).map(|(unit, _)| unit)
+    }
+
+    /// 指定した点のレイヤーを返す．透明な点，およびスプライト外の点については`None`を返す．
+    pub fn layer_at(&self, position: CanvasItemPosition) -> Option<L> {
+        self.cell_at(position).map(|(_, layer)| layer)
+    }
+
+    /// 透明でないすべての点について，その位置，描画単位およびレイヤーを行優先で列挙する．
+    pub fn units(&self) -> impl Iterator<Item = (CanvasItemPosition, DrawableUnit, L)> + '_ {
+        let width = self.size.x;
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, cell)| {
+                cell.map(|(unit, layer)| {
+                    let position = CanvasItemPosition::new(index % width, index / width);
+                    (position, unit, layer)
+                })
+            })
+    }
+
+    fn cell_at(&self, position: CanvasItemPosition) -> Option<(DrawableUnit, L)> {
+        if position.x < self.size.x && position.y < self.size.y {
+            self.cells[position.y * self.size.x + position.x]
+        } else {
+            None
+        }
+    }
+}
+
+impl<L: Layer> Canvas<L> {
+    /// アスキーアートから，その大きさに合わせたキャンバスを生成する．
+    /// 描画単位への変換規則は`Sprite::from_ascii_art`と同じである．
+    /// # Panics on Debug Build
+    /// コンソールへの描画時に幅が1か2以外の文字が含まれる場合
+    pub fn from_ascii_art(art: &str, legend: &Legend<L>) -> Self
+    where
+        L: Default,
+    {
+        let sprite = Sprite::from_ascii_art(art, legend);
+        let mut canvas = Canvas::with_size(sprite.size());
+        canvas.draw_sprite(&sprite, CanvasItemPosition::new(0, 0));
+        canvas
+    }
+
+    /// スプライトを，その左上の点が`position`となるように描画する．
+    /// 透明な点およびキャンバス外にはみ出した点は描画されない．
+    /// 各点の描画内容は，`draw_unit`と同じくレイヤーの優先度に従って更新される．
+    pub fn draw_sprite(&mut self, sprite: &Sprite<L>, position: CanvasItemPosition) {
+        for (offset, unit, layer) in sprite.units() {
+            // 座標が桁あふれする点もキャンバス外として扱う
+            let destination = match (
+                position.x.checked_add(offset.x),
+                position.y.checked_add(offset.y),
+            ) {
+                (Some(x), Some(y)) => CanvasItemPosition::new(x, y),
+                _ => continue,
+            };
+            if self.is_drawable_at(destination) {
+                self.draw_unit(unit, destination, layer);
+            }
+        }
+    }
+}
+
+/// 凡例に従い，描画単位の文字色および背景色と，レイヤーを決定する．
+/// 半角空白2つからなる描画単位は透明な点として`None`を返す．
+fn apply_legend<L: Layer + Default>(
+    unit: DrawableUnit,
+    legend: &Legend<L>,
+) -> Option<(DrawableUnit, L)> {
+    let mut chars = unit.chars();
+    let key = chars.next().expect("Drawable unit has at least one char.");
+    if key == ' ' && chars.next() == Some(' ') {
+        return None;
+    }
+    match legend.entry(key) {
+        Some(entry) => {
+            let unit = unit.with_color(entry.color.unwrap_or(UnitColor::White));
+            let unit = match entry.background {
+                Some(background) => unit.with_background(background),
+                None => unit,
+            };
+            Some((unit, entry.layer.unwrap_or_default()))
+        }
+        None => Some((unit, L::default())),
+    }
+}
+
+/// アスキーアートを行に分割する．先頭の空行および末尾の空白のみの行は取り除かれる．
+/// 改行は`\n`と`\r\n`のいずれでもよい．
+pub(crate) fn ascii_art_rows(art: &str) -> impl Iterator<Item = &str> {
+    let art = art
+        .strip_prefix("\r\n")
+        .or_else(|| art.strip_prefix('\n'))
+        .unwrap_or(art);
+    let art = art.trim_end_matches(&[' ', '\r', '\n'][..]);
+    art.split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .filter(move |_| !art.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_legend() -> Legend<i32> {
+        Legend::new()
+            .with_color('#', UnitColor::White)
+            .with_layer('#', 1)
+            .with_color('～', UnitColor::Blue)
+            .with_background('～', UnitColor::Cyan)
+    }
+
+    #[test]
+    fn test_from_ascii_art() {
+        let canvas = Canvas::from_ascii_art(
+            "
+####
+  ab～
+",
+            &sample_legend(),
+        );
+        assert_eq!(Pair::new(3, 2), canvas.size());
+        let position = |x, y| CanvasItemPosition::new(x, y);
+        assert_eq!(
+            Some(DrawableUnit::from_double_half_char(
+                '#',
+                '#',
+                UnitColor::White
+            )),
+            canvas.unit_at(position(0, 0))
+        );
+        assert_eq!(Some(1), canvas.layer_at(position(1, 0)));
+        // 凡例にない文字は白，レイヤーは既定値
+        assert_eq!(
+            Some(DrawableUnit::from_double_half_char(
+                'a',
+                'b',
+                UnitColor::White
+            )),
+            canvas.unit_at(position(1, 1))
+        );
+        assert_eq!(Some(0), canvas.layer_at(position(1, 1)));
+        assert_eq!(
+            Some(
+                DrawableUnit::from_single_full_char('～', UnitColor::Blue)
+                    .with_background(UnitColor::Cyan)
+            ),
+            canvas.unit_at(position(2, 1))
+        );
+        // 半角空白2つは何も描画しない
+        assert_eq!(None, canvas.unit_at(position(0, 1)));
+        // 行の長さが足りない点も何も描画しない
+        assert_eq!(None, canvas.unit_at(position(2, 0)));
+
+        assert_eq!("####  \n  ab～", canvas.to_plain_string(false));
+    }
+
+    #[test]
+    fn test_crlf_line_breaks() {
+        let sprite = Sprite::from_ascii_art("##\r\n##", &sample_legend());
+        assert_eq!(Sprite::from_ascii_art("##\n##", &sample_legend()), sprite);
+        assert_eq!(Pair::new(1, 2), sprite.size());
+        // 複数行の文字列リテラルと同じく，先頭の空行と末尾の改行は取り除かれる
+        assert_eq!(
+            sprite,
+            Sprite::from_ascii_art("\r\n##\r\n##\r\n", &sample_legend())
+        );
+    }
+
+    #[test]
+    fn test_pairing_matches_create_units_from() {
+        let art = "aあbc";
+        let sprite = Sprite::<i32>::from_ascii_art(art, &Legend::new());
+        let expected = DrawableUnit::create_units_from(art, UnitColor::White);
+        assert_eq!(
+            expected,
+            sprite.units().map(|(_, unit, _)| unit).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_draw_sprite() {
+        let sprite = Sprite::from_ascii_art("##\n  ##", &sample_legend());
+        let mut canvas = Canvas::with_size(Pair::new(3, 3));
+        let higher = DrawableUnit::from_double_half_char('@', ' ', UnitColor::Red);
+        canvas.draw_unit(higher, CanvasItemPosition::new(1, 1), 2);
+        canvas.draw_sprite(&sprite, CanvasItemPosition::new(1, 1));
+        // はみ出した部分は描画されず，上位のレイヤーは上書きされない
+        assert_eq!(Some(higher), canvas.unit_at(CanvasItemPosition::new(1, 1)));
+        assert_eq!(Some(1), canvas.layer_at(CanvasItemPosition::new(2, 2)));
+        assert_eq!(None, canvas.unit_at(CanvasItemPosition::new(1, 2)));
+        assert_eq!(2, canvas.units().count());
+    }
+
+    #[test]
+    fn test_draw_sprite_near_max_position() {
+        let sprite = Sprite::from_ascii_art("##\n  ##", &sample_legend());
+        let mut canvas = Canvas::with_size(Pair::new(3, 3));
+        canvas.draw_sprite(&sprite, CanvasItemPosition::new(usize::MAX, 0));
+        canvas.draw_sprite(&sprite, CanvasItemPosition::new(0, usize::MAX));
+        assert_eq!(0, canvas.units().count());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{ColorSupport, DrawDestination, UnitColor};
+use std::fmt;
+use std::ops::{BitOr, BitOrAssign};
+
+/// 描画単位に適用する文字装飾の集合を表す．
+/// 複数の装飾は`|`演算子で組み合わせることができる．
+/// # Examples
+/// ```rust
+/// use cui_gaming::UnitAttributes;
+///
+/// let attributes = UnitAttributes::BOLD | UnitAttributes::UNDERLINE;
+/// assert!(attributes.contains(UnitAttributes::BOLD));
+/// assert!(!attributes.contains(UnitAttributes::BLINK));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct UnitAttributes(u8);
+
+/// 描画単位の書き込み時に適用される，文字色，背景色および文字装飾の組．
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct UnitStyle {
+    /// 文字色．`None`の場合は端末の既定の文字色．
+    pub foreground: Option<UnitColor>,
+    /// 背景色．`None`の場合は端末の既定の背景色．
+    pub background: Option<UnitColor>,
+    /// 文字装飾．
+    pub attributes: UnitAttributes,
+}
+
+impl UnitAttributes {
+    /// 装飾なし．
+    pub const NONE: Self = Self(0);
+    /// 太字．
+    pub const BOLD: Self = Self(1 << 0);
+    /// 低輝度．
+    pub const DIM: Self = Self(1 << 1);
+    /// 下線．
+    pub const UNDERLINE: Self = Self(1 << 2);
+    /// 文字色と背景色の反転．
+    pub const REVERSE: Self = Self(1 << 3);
+    /// 点滅．
+    pub const BLINK: Self = Self(1 << 4);
+
+    /// 装飾が1つも含まれていないか返す．
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// 指定した装飾がすべて含まれているか返す．
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// この集合から，指定した装飾を取り除いたものを返す．
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    /// 2つの装飾の集合の和を返す．
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// この集合に含まれる各装飾を指定するSGRパラメータを，それぞれ`;`を前置して書き込む．
+    pub(crate) fn write_parameters<D: DrawDestination>(self, destination: &mut D) -> fmt::Result {
+        const PARAMETERS: [(UnitAttributes, u8); 5] = [
+            (UnitAttributes::BOLD, 1),
+            (UnitAttributes::DIM, 2),
+            (UnitAttributes::UNDERLINE, 4),
+            (UnitAttributes::BLINK, 5),
+            (UnitAttributes::REVERSE, 7),
+        ];
+        for &(attribute, parameter) in PARAMETERS.iter() {
+            if self.contains(attribute) {
+                write!(destination, ";{}", parameter)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl UnitStyle {
+    /// 色および装飾をいずれも指定しない，端末の既定のスタイルを返す．
+    pub const fn plain() -> Self {
+        Self {
+            foreground: None,
+            background: None,
+            attributes: UnitAttributes::NONE,
+        }
+    }
+
+    /// 色および装飾がいずれも指定されていないか返す．
+    pub fn is_plain(&self) -> bool {
+        *self == Self::plain()
+    }
+
+    /// 色を指定した色表現範囲に変換したスタイルを返す．
+    /// 変換されるのは色のみであり，装飾はそのまま保たれる．
+    /// `ColorSupport::Monochrome`の場合，文字色および背景色は端末の既定の色となる．
+    pub fn downgrade(self, color_support: ColorSupport) -> Self {
+        Self {
+            foreground: self
+                .foreground
+                .and_then(|color| color.downgrade(color_support)),
+            background: self
+                .background
+                .and_then(|color| color.downgrade(color_support)),
+            attributes: self.attributes,
+        }
+    }
+
+    /// 端末の状態をこのスタイルに切り替えるSGRシーケンスを書き込む．
+    /// シーケンスは直前のスタイルをリセットしてから適用するため，直前のスタイルによらず同じ結果となる．
+    pub(crate) fn write_sgr_to<D: DrawDestination>(&self, destination: &mut D) -> fmt::Result {
+        destination.write_str("\x1b[0")?;
+        if let Some(foreground) = self.foreground {
+            foreground.write_foreground_parameters(destination)?;
+        }
+        if let Some(background) = self.background {
+            background.write_background_parameters(destination)?;
+        }
+        self.attributes.write_parameters(destination)?;
+        destination.write_char('m')
+    }
+
+    /// 端末の状態を既定のスタイルに戻すSGRシーケンスを書き込む．
+    pub(crate) fn write_reset_to<D: DrawDestination>(destination: &mut D) -> fmt::Result {
+        destination.write_str("\x1b[0m")
+    }
+}
+
+impl BitOr for UnitAttributes {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+impl BitOrAssign for UnitAttributes {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = self.union(rhs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_contains() {
+        let mut attributes = UnitAttributes::NONE;
+        assert!(attributes.is_empty());
+        assert!(attributes.contains(UnitAttributes::NONE));
+        attributes |= UnitAttributes::DIM;
+        attributes |= UnitAttributes::REVERSE;
+        assert!(!attributes.is_empty());
+        assert!(attributes.contains(UnitAttributes::DIM | UnitAttributes::REVERSE));
+        assert!(!attributes.contains(UnitAttributes::DIM | UnitAttributes::BOLD));
+        assert!(!attributes.contains(UnitAttributes::UNDERLINE));
+    }
+
+    #[test]
+    fn downgrade_keeps_attributes() {
+        let style = UnitStyle {
+            foreground: Some(UnitColor::Red),
+            background: Some(UnitColor::Blue),
+            attributes: UnitAttributes::BOLD | UnitAttributes::UNDERLINE,
+        };
+        let monochrome = style.downgrade(ColorSupport::Monochrome);
+        assert_eq!(None, monochrome.foreground);
+        assert_eq!(None, monochrome.background);
+        assert_eq!(style.attributes, monochrome.attributes);
+        assert_eq!(style, style.downgrade(ColorSupport::Ansi16));
+    }
+}
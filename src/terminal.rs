@@ -0,0 +1,407 @@
+extern crate console;
+
+use crate::{
+    Canvas, CanvasLattice, CastRecorder, ColorSupport, DiffRenderer, DrawError, FrameWriter,
+    KeyboardInput, Layer,
+};
+use data_structure::Pair;
+use std::io::{self, Write};
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+/// コンソール上の文字セルを単位とした座標の成分となる型．
+pub type TerminalLattice = usize;
+
+/// 代替スクリーンに切り替え，カーソルを非表示にして画面を消去するエスケープシーケンス．
+const ENTER_SEQUENCE: &str = "\x1b[?1049h\x1b[?25l\x1b[2J\x1b[H";
+/// スタイルをリセットし，カーソルを表示して代替スクリーンから戻るエスケープシーケンス．
+const LEAVE_SEQUENCE: &str = "\x1b[0m\x1b[?25h\x1b[?1049l";
+
+/// 標準出力を用いる`TerminalSession`が有効であるか．同時に有効にできるセッションは1つのみである．
+static SESSION_ACTIVE: AtomicBool = AtomicBool::new(false);
+/// 端末の状態を復元するパニックフックが登録されているか．
+static PANIC_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// ゲームの実行中に端末の状態を管理する．
+///
+/// 生成時に代替スクリーンへ切り替えてカーソルを非表示にし，破棄時にこれらを元に戻す．
+/// `start`で開始したセッションは，さらに端末のrawモード (行バッファリングおよびエコーの無効化)を管理し，
+/// セッションが有効な間にパニックが発生した場合も，登録されたパニックフックによって端末の状態が復元される．
+///
+/// キー入力は`input`で取得できる．
+pub struct TerminalSession<W: Write = io::Stdout> {
+    /// 端末へのフレームの書き込みを行う．
+    writer: FrameWriter<W>,
+    /// 前回表示したフレームとの差分を書き込む．
+    renderer: DiffRenderer,
+    /// キー入力．
+    input: KeyboardInput,
+    /// 表示したフレームの記録先．
+    recorder: Option<CastRecorder<Box<dyn Write>>>,
+    /// 標準出力の端末を管理しているか．`true`の場合，破棄時にrawモードを解除する．
+    owns_terminal: bool,
+    /// 登録したパニックフックを取り除き，登録前のフックに戻す関数．
+    restore_panic_hook: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl TerminalSession {
+    /// 標準出力の端末をrawモードおよび代替スクリーンに切り替え，カーソルを非表示にしてセッションを開始する．
+    /// セッションが有効な間は，端末の状態を復元するパニックフックが登録される．
+    /// このフックは登録前のパニックフックを呼び出し，セッションの終了時に取り除かれる．
+    /// # Returns
+    /// すでに別のセッションが有効である場合や，端末の設定または書き込みに失敗した場合はエラーを返す．
+    pub fn start() -> io::Result<Self> {
+        if SESSION_ACTIVE.swap(true, Ordering::SeqCst) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "another terminal session is already active",
+            ));
+        }
+        if let Err(e) = raw_mode::enable() {
+            SESSION_ACTIVE.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+        let restore_panic_hook = install_panic_hook();
+        match Self::with_writer(io::stdout()) {
+            Ok(mut session) => {
+                session.owns_terminal = true;
+                session.restore_panic_hook = restore_panic_hook;
+                Ok(session)
+            }
+            Err(e) => {
+                if let Some(restore_panic_hook) = restore_panic_hook {
+                    restore_panic_hook();
+                }
+                restore_terminal();
+                SESSION_ACTIVE.store(false, Ordering::SeqCst);
+                Err(e)
+            }
+        }
+    }
+}
+
+impl<W: Write> TerminalSession<W> {
+    /// 指定した出力先を端末とみなし，代替スクリーンへの切り替えとカーソルの非表示を書き込んでセッションを開始する．
+    /// 破棄時には，出力先にこれらを元に戻すエスケープシーケンスが書き込まれる．
+    ///
+    /// `start`と異なり，端末のrawモードやパニックフックは変更しない．
+    /// # Returns
+    /// 出力先への書き込みに失敗した場合はエラーを返す．
+    pub fn with_writer(writer: W) -> io::Result<Self> {
+        Self::with_writer_and_color_support(writer, ColorSupport::current())
+    }
+
+    /// 指定した出力先を端末とみなし，指定した色表現範囲でフレームを表示するセッションを開始する．
+    /// 出力先に書き込まれる内容は`with_writer`と同じである．
+    /// # Returns
+    /// 出力先への書き込みに失敗した場合はエラーを返す．
+    pub fn with_writer_and_color_support(
+        mut writer: W,
+        color_support: ColorSupport,
+    ) -> io::Result<Self> {
+        writer.write_all(ENTER_SEQUENCE.as_bytes())?;
+        writer.flush()?;
+        Ok(Self {
+            writer: FrameWriter::with_color_support(writer, color_support),
+            renderer: DiffRenderer::new(),
+            input: KeyboardInput::new(),
+            recorder: None,
+            owns_terminal: false,
+            restore_panic_hook: None,
+        })
+    }
+
+    /// キャンバスの内容を端末に表示する．
+    /// 前回表示した内容から変化した部分のみが書き込まれる．
+    /// 記録中の場合，書き込んだ内容は，改行を`\r\n`に変換したうえでasciicastの出力イベントとして記録される．
+    pub fn present<L: Layer>(&mut self, canvas: &Canvas<L>) -> Result<(), DrawError> {
+        self.writer.render(&mut self.renderer, canvas)?;
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record_frame(self.writer.last_frame())?;
+        }
+        Ok(())
+    }
+
+    /// 以降に表示するフレームを，asciicast v2形式で`writer`に記録し始める．
+    /// 記録が再生時に画面全体を再現できるよう，次回の`present`では画面全体が書き直される．
+    /// すでに記録中の場合，以前の記録は終了する．
+    pub fn start_recording<R: Write + 'static>(&mut self, writer: R) -> io::Result<()> {
+        let size = terminal_size().unwrap_or_else(|| Pair::new(80, 24));
+        let writer: Box<dyn Write> = Box::new(writer);
+        self.recorder = Some(CastRecorder::new(writer, size)?);
+        self.request_full_redraw();
+        Ok(())
+    }
+
+    /// 記録を終了する．記録中でなかった場合は何もしない．
+    pub fn stop_recording(&mut self) {
+        self.recorder = None;
+    }
+
+    /// 次回の`present`で，画面全体を書き直すよう要求する．
+    /// 端末のサイズが変わった場合など，画面の内容が崩れた可能性がある場合に用いる．
+    pub fn request_full_redraw(&mut self) {
+        self.renderer.request_full_redraw();
+    }
+
+    /// キー入力を返す．
+    pub fn input(&self) -> &KeyboardInput {
+        &self.input
+    }
+}
+
+impl<W: Write> Drop for TerminalSession<W> {
+    fn drop(&mut self) {
+        // パニックの処理中はパニックフックを変更できないため，フックは登録されたままとなる
+        if let Some(restore_panic_hook) = self.restore_panic_hook.take() {
+            if !thread::panicking() {
+                restore_panic_hook();
+            }
+        }
+        if self.owns_terminal {
+            // パニックフックによってすでに復元されている場合は何もしない
+            if SESSION_ACTIVE.swap(false, Ordering::SeqCst) {
+                restore_terminal();
+            }
+        } else {
+            // 復元処理の失敗は無視される
+            let writer = self.writer.get_mut();
+            let _ = writer.write_all(LEAVE_SEQUENCE.as_bytes());
+            let _ = writer.flush();
+        }
+    }
+}
+
+/// 端末の状態を復元するパニックフックを，それまでのフックを呼び出すように登録する．
+/// # Returns
+/// 登録したフックを取り除き，登録前のフックに戻す関数を返す．
+/// ただし，登録後に他のフックが設定されていた場合，その関数は現在のフックをそのまま残す．
+/// 以前のセッションのフックが取り除かれずに残っている場合は，新たなフックを登録せずに`None`を返す．
+fn install_panic_hook() -> Option<Box<dyn FnOnce() + Send>> {
+    if PANIC_HOOK_INSTALLED.swap(true, Ordering::SeqCst) {
+        return None;
+    }
+    let previous_hook = Arc::new(panic::take_hook());
+    let chained_hook = Arc::clone(&previous_hook);
+    panic::set_hook(Box::new(move |info| {
+        // パニックメッセージが通常のスクリーンに表示されるよう，先に端末を復元する
+        if SESSION_ACTIVE.swap(false, Ordering::SeqCst) {
+            restore_terminal();
+        }
+        chained_hook(info);
+    }));
+    // 取り除く際に現在のフックが登録したものか識別できるよう，そのアドレスを記録する
+    let installed_hook = panic::take_hook();
+    let installed_address = hook_address(&*installed_hook);
+    panic::set_hook(installed_hook);
+    Some(Box::new(move || {
+        let current_hook = panic::take_hook();
+        // 登録したフックが破棄されていない間は，他のフックが同じアドレスを持つことはない
+        let is_installed_hook = Arc::strong_count(&previous_hook) == 2
+            && hook_address(&*current_hook) == installed_address;
+        if is_installed_hook {
+            // 登録したフックを破棄することで，登録前のフックへの参照はここで保持するもののみとなる
+            drop(current_hook);
+            if let Ok(previous_hook) = Arc::try_unwrap(previous_hook) {
+                panic::set_hook(previous_hook);
+            }
+        } else {
+            // 登録後にアプリケーションが設定したフックは取り除かない
+            panic::set_hook(current_hook);
+        }
+        PANIC_HOOK_INSTALLED.store(false, Ordering::SeqCst);
+    }))
+}
+
+/// パニックフックを識別するためのアドレスを返す．
+fn hook_address<T: ?Sized>(hook: &T) -> usize {
+    hook as *const T as *const () as usize
+}
+
+/// 標準出力の端末を`TerminalSession::start`の呼び出し前の状態に戻す．
+/// 復元処理の失敗は無視される．
+fn restore_terminal() {
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(LEAVE_SEQUENCE.as_bytes());
+    let _ = stdout.flush();
+    raw_mode::disable();
+}
+
+/// 標準入力の端末のrawモードを管理する．
+/// 出力の改行の変換は維持されるため，rawモードの間も`'\n'`で行頭に戻る．
+#[cfg(unix)]
+mod raw_mode {
+    use std::io;
+    use std::sync::{Mutex, PoisonError};
+
+    /// rawモードを有効にする前の端末の設定．rawモードでない場合は`None`．
+    static ORIGINAL: Mutex<Option<libc::termios>> = Mutex::new(None);
+
+    /// 行バッファリングおよび入力のエコーを無効にする．
+    pub(super) fn enable() -> io::Result<()> {
+        let mut original = ORIGINAL.lock().unwrap_or_else(PoisonError::into_inner);
+        // 端末の設定は，tcgetattrによってすべて初期化される
+        let mut termios = unsafe { std::mem::zeroed::<libc::termios>() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut termios) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let previous = termios;
+        termios.c_lflag &= !(libc::ICANON | libc::ECHO);
+        termios.c_cc[libc::VMIN] = 1;
+        termios.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // すでにrawモードである場合は，最初に有効にする前の設定を保持する
+        original.get_or_insert(previous);
+        Ok(())
+    }
+
+    /// 端末の設定をrawモードを有効にする前の状態に戻す．rawモードでない場合は何もしない．
+    pub(super) fn disable() {
+        let mut original = ORIGINAL.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(termios) = original.take() {
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios);
+            }
+        }
+    }
+}
+
+/// rawモードに対応していない環境では，端末の設定を変更しない．
+#[cfg(not(unix))]
+mod raw_mode {
+    use std::io;
+
+    pub(super) fn enable() -> io::Result<()> {
+        Ok(())
+    }
+
+    pub(super) fn disable() {}
+}
+
+/// 現在の端末のサイズを，文字セル単位で返す．
+/// # Returns
+/// 端末の列数を`x`，行数を`y`とした組を`Some`として返す．
+///
+/// 標準出力が端末に接続されていない場合など，サイズを取得できない場合は`None`を返す．
+pub fn terminal_size() -> Option<Pair<TerminalLattice>> {
+    console::Term::stdout()
+        .size_checked()
+        .map(|(rows, columns)| Pair::new(columns as TerminalLattice, rows as TerminalLattice))
+}
+
+/// 指定したサイズの端末に，枠を含めて収まる最大のキャンバスのサイズを返す．
+/// 各`DrawableUnit`は端末上の2列を占有するため，キャンバスの幅は端末の列数の半分をもとに決まる．
+/// # Params
+/// 1. `terminal_size` 端末のサイズ (文字セル単位)．
+/// 1. `border_thickness` キャンバスの枠の厚さ (`DrawableUnit`単位)．
+pub fn canvas_size_for_terminal(
+    terminal_size: Pair<TerminalLattice>,
+    border_thickness: CanvasLattice,
+) -> Pair<CanvasLattice> {
+    let width = (terminal_size.x / 2).saturating_sub(border_thickness * 2);
+    let height = terminal_size.y.saturating_sub(border_thickness * 2);
+    Pair::new(width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::vt100::VirtualTerminal;
+    use crate::{DrawableUnit, UnitColor};
+    #[test]
+    fn test_canvas_size_for_terminal() {
+        assert_eq!(
+            Pair::new(38, 22),
+            canvas_size_for_terminal(Pair::new(80, 24), 1)
+        );
+        // 奇数列の場合，余った1列は使われない
+        assert_eq!(
+            Pair::new(38, 22),
+            canvas_size_for_terminal(Pair::new(81, 24), 1)
+        );
+        assert_eq!(
+            Pair::new(40, 24),
+            canvas_size_for_terminal(Pair::new(80, 24), 0)
+        );
+        // 枠すら収まらない端末では，サイズ0のキャンバスとなる
+        assert_eq!(
+            Pair::new(0, 0),
+            canvas_size_for_terminal(Pair::new(3, 1), 1)
+        );
+    }
+
+    #[test]
+    fn session_enters_and_leaves_alternate_screen() {
+        let mut output = Vec::new();
+        {
+            let session = TerminalSession::with_writer(&mut output).unwrap();
+            drop(session);
+        }
+        let output = String::from_utf8(output).unwrap();
+        let enter = output.find("\x1b[?1049h").unwrap();
+        let hide_cursor = output.find("\x1b[?25l").unwrap();
+        let show_cursor = output.find("\x1b[?25h").unwrap();
+        let leave = output.find("\x1b[?1049l").unwrap();
+        assert!(enter < hide_cursor);
+        assert!(hide_cursor < show_cursor);
+        assert!(show_cursor < leave);
+        assert!(output.ends_with(LEAVE_SEQUENCE));
+    }
+
+    #[test]
+    fn session_restores_terminal_on_drop() {
+        let mut output = Vec::new();
+        {
+            let mut session = TerminalSession::with_writer(&mut output).unwrap();
+            let mut canvas = Canvas::with_size(Pair::new(2, 1));
+            canvas.draw_unit(
+                DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red),
+                Pair::new(0, 0),
+                0,
+            );
+            session.present(&canvas).unwrap();
+        }
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with(ENTER_SEQUENCE));
+        // フレームの後に，スタイルのリセット，カーソルの表示および代替スクリーンからの復帰が書き込まれる
+        let frame_end = output.rfind("ab").unwrap();
+        assert_eq!(
+            LEAVE_SEQUENCE,
+            &output[output.len() - LEAVE_SEQUENCE.len()..]
+        );
+        assert!(frame_end < output.len() - LEAVE_SEQUENCE.len());
+        let mut terminal = VirtualTerminal::new(8, 3);
+        terminal.feed(&output);
+        assert!(terminal.is_cursor_visible());
+        assert!(!terminal.is_alternate_screen());
+        assert!(terminal.current_style().is_plain());
+    }
+
+    #[test]
+    fn removing_panic_hook_keeps_later_hook() {
+        let current_address = || {
+            let hook = panic::take_hook();
+            let address = hook_address(&*hook);
+            panic::set_hook(hook);
+            address
+        };
+        // 登録したフックが現在のフックであれば，登録前のフックに戻す
+        let original = current_address();
+        let remove = install_panic_hook().unwrap();
+        assert_ne!(original, current_address());
+        remove();
+        assert_eq!(original, current_address());
+
+        // 登録後に設定されたフックは取り除かない
+        let remove = install_panic_hook().unwrap();
+        panic::set_hook(Box::new(|_| {}));
+        let application = current_address();
+        remove();
+        assert_eq!(application, current_address());
+        drop(panic::take_hook());
+    }
+}
@@ -0,0 +1,328 @@
+//! 描画結果を検査するテスト用の機能を提供する．
+
+pub mod vt100;
+
+use crate::sprite::ascii_art_rows;
+use crate::{Canvas, CanvasItemPosition, CanvasLattice, DrawableUnit, Layer, Legend, UnitColor};
+use data_structure::Pair;
+use std::fmt::{self, Write};
+
+/// キャンバスに描画された内容のスナップショット．
+/// 枠は含まず，キャンバス内の各点の描画単位とレイヤーを保持する．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedCanvas<L> {
+    /// キャンバスのサイズ．
+    size: Pair<CanvasLattice>,
+    /// 各点の描画単位とレイヤー．行優先で格納される．
+    cells: Vec<Option<(DrawableUnit, L)>>,
+}
+
+impl<L: Layer> RenderedCanvas<L> {
+    /// キャンバスに現在描画されている内容のスナップショットを返す．
+    pub fn from_canvas(canvas: &Canvas<L>) -> Self {
+        let size = canvas.size();
+        let cells = (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| CanvasItemPosition::new(x, y)))
+            .map(|position| {
+                canvas
+                    .unit_at(position)
+                    .and_then(|unit| canvas.layer_at(position).map(|layer| (unit, layer)))
+            })
+            .collect();
+        Self { size, cells }
+    }
+
+    /// すべての点に何も描画されていない，指定したサイズのスナップショットを返す．
+    pub(crate) fn blank(size: Pair<CanvasLattice>) -> Self {
+        Self {
+            size,
+            cells: vec![None; size.x * size.y],
+        }
+    }
+
+    /// 指定した点の描画単位とレイヤーを設定する．
+    /// # Returns
+    /// 指定した点がキャンバス外にある場合は，何もせずに`false`を返す．
+    pub(crate) fn set_cell(
+        &mut self,
+        position: CanvasItemPosition,
+        cell: (DrawableUnit, L),
+    ) -> bool {
+        if position.x < self.size.x && position.y < self.size.y {
+            self.cells[position.y * self.size.x + position.x] = Some(cell);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// キャンバスのサイズを返す．
+    pub fn size(&self) -> Pair<CanvasLattice> {
+        self.size
+    }
+
+    /// 指定した点の描画単位とレイヤーを返す．
+    /// 何も描画されていない点，およびキャンバス外の点については`None`を返す．
+    pub fn cell(&self, position: CanvasItemPosition) -> Option<(DrawableUnit, L)> {
+        if position.x < self.size.x && position.y < self.size.y {
+            self.cells[position.y * self.size.x + position.x]
+        } else {
+            None
+        }
+    }
+
+    /// 指定した点に表示される文字列を返す．何も描画されていない点は空白2文字となる．
+    fn glyphs_at(&self, position: CanvasItemPosition) -> String {
+        match self.cell(position) {
+            Some((unit, _)) => unit.chars().collect(),
+            None => "  ".to_string(),
+        }
+    }
+}
+
+impl<L: Layer> fmt::Display for RenderedCanvas<L> {
+    /// 各行の文字を，スタイルを含めずに書き込む．各行は`\n`で区切られる．
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.size.y {
+            if y > 0 {
+                f.write_char('\n')?;
+            }
+            for x in 0..self.size.x {
+                f.write_str(&self.glyphs_at(CanvasItemPosition::new(x, y)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 描画単位の列から，スタイルを含まない文字列を生成して返す．
+pub fn string_without_style(units: &[DrawableUnit]) -> String {
+    units.iter().flat_map(DrawableUnit::chars).collect()
+}
+
+/// キャンバスの内容が，アスキーアートで表したパターンと一致することを検査する．
+///
+/// パターンの各行はキャンバスの各行に対応し，`DrawableUnit::create_units_from`と同じ規則で描画単位に分割される．
+/// 空白2文字は，何も描画されていない点とも一致する．
+/// パターンの先頭の空行および末尾の空白のみの行は無視されるため，複数行の文字列リテラルをそのまま用いることができる．
+///
+/// 凡例を指定した場合，凡例に含まれる文字 (描画単位の左側の文字)の点について，文字色，背景色およびレイヤーも検査する．
+/// # Panics
+/// キャンバスの内容がパターンと一致しない場合．パニックメッセージには，一致しない点の一覧が含まれる．
+/// # Examples
+/// ```rust
+/// use cui_gaming::testing::assert_canvas_matches;
+/// use cui_gaming::{Canvas, DrawableUnit, Legend, UnitColor};
+/// use data_structure::Pair;
+///
+/// let mut canvas = Canvas::with_size(Pair::new(3, 2));
+/// let wall = DrawableUnit::from_double_half_char('[', ']', UnitColor::White);
+/// canvas.draw_unit(wall, Pair::new(0, 0), 1);
+/// canvas.draw_unit(wall, Pair::new(2, 1), 1);
+/// assert_canvas_matches(
+///     &canvas,
+///     "
+/// []
+///     []
+/// ",
+///     Some(&Legend::new().with_color('[', UnitColor::White).with_layer('[', 1)),
+/// );
+/// ```
+pub fn assert_canvas_matches<L: Layer + fmt::Debug>(
+    canvas: &Canvas<L>,
+    pattern: &str,
+    legend: Option<&Legend<L>>,
+) {
+    if let Err(message) = check_canvas_matches(canvas, pattern, legend) {
+        panic!("{}", message);
+    }
+}
+
+/// キャンバスの内容がパターンと一致するか検査する．
+/// # Returns
+/// 一致しない場合は，その内容を説明するメッセージを返す．
+fn check_canvas_matches<L: Layer + fmt::Debug>(
+    canvas: &Canvas<L>,
+    pattern: &str,
+    legend: Option<&Legend<L>>,
+) -> Result<(), String> {
+    let rendered = RenderedCanvas::from_canvas(canvas);
+    let size = rendered.size();
+    let expected_rows = ascii_art_rows(pattern)
+        .map(|line| DrawableUnit::create_units_from(line, UnitColor::White))
+        .collect::<Vec<_>>();
+    let mut mismatches = vec![];
+    if expected_rows.len() > size.y {
+        mismatches.push(format!(
+            "pattern has {} rows, but canvas has only {} rows",
+            expected_rows.len(),
+            size.y
+        ));
+    }
+    for (y, row) in expected_rows.iter().enumerate().take(size.y) {
+        if row.len() > size.x {
+            mismatches.push(format!(
+                "row {}: pattern has {} units, but canvas has only {} columns",
+                y,
+                row.len(),
+                size.x
+            ));
+        }
+    }
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let position = CanvasItemPosition::new(x, y);
+            let expected = expected_rows.get(y).and_then(|row| row.get(x));
+            let expected_glyphs = expected
+                .map(|unit| unit.chars().collect::<String>())
+                .unwrap_or_else(|| "  ".to_string());
+            let actual_glyphs = rendered.glyphs_at(position);
+            if expected_glyphs != actual_glyphs {
+                mismatches.push(format!(
+                    "({}, {}): expected {:?}, found {:?}",
+                    x, y, expected_glyphs, actual_glyphs
+                ));
+                continue;
+            }
+            let entry = expected
+                .and_then(|unit| unit.chars().next())
+                .and_then(|c| legend.and_then(|legend| legend.entry(c)));
+            let (entry, (unit, layer)) = match (entry, rendered.cell(position)) {
+                (Some(entry), Some(cell)) => (entry, cell),
+                _ => continue,
+            };
+            if let Some(color) = entry.color {
+                if color != unit.color() {
+                    mismatches.push(format!(
+                        "({}, {}) {:?}: expected color {:?}, found {:?}",
+                        x,
+                        y,
+                        expected_glyphs,
+                        color,
+                        unit.color()
+                    ));
+                }
+            }
+            if let Some(background) = entry.background {
+                if Some(background) != unit.background() {
+                    mismatches.push(format!(
+                        "({}, {}) {:?}: expected background {:?}, found {:?}",
+                        x,
+                        y,
+                        expected_glyphs,
+                        background,
+                        unit.background()
+                    ));
+                }
+            }
+            if let Some(expected_layer) = entry.layer {
+                if expected_layer != layer {
+                    mismatches.push(format!(
+                        "({}, {}) {:?}: expected layer {:?}, found {:?}",
+                        x, y, expected_glyphs, expected_layer, layer
+                    ));
+                }
+            }
+        }
+    }
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        let expected = ascii_art_rows(pattern).collect::<Vec<_>>().join("\n");
+        Err(format!(
+            "canvas does not match the pattern ({} mismatches)\n{}\n--- expected ---\n{}\n--- actual ---\n{}",
+            mismatches.len(),
+            mismatches.join("\n"),
+            expected,
+            rendered
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_canvas() -> Canvas<i32> {
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        let wall = DrawableUnit::from_double_half_char('#', '#', UnitColor::White);
+        let water = DrawableUnit::from_single_full_char('～', UnitColor::Blue);
+        canvas.draw_unit(wall, CanvasItemPosition::new(0, 0), 1);
+        canvas.draw_unit(wall, CanvasItemPosition::new(1, 0), 1);
+        canvas.draw_unit(water, CanvasItemPosition::new(2, 1), 0);
+        canvas
+    }
+
+    #[test]
+    fn matches_pattern() {
+        let canvas = sample_canvas();
+        assert_canvas_matches(&canvas, "####\n    ～", None);
+        let legend = Legend::new()
+            .with_color('#', UnitColor::White)
+            .with_layer('#', 1)
+            .with_color('～', UnitColor::Blue)
+            .with_layer('～', 0);
+        assert_canvas_matches(
+            &canvas,
+            "
+####
+    ～
+",
+            Some(&legend),
+        );
+    }
+
+    #[test]
+    fn reports_glyph_mismatch() {
+        let canvas = sample_canvas();
+        let message = check_canvas_matches(&canvas, "##  \n    ～", None).unwrap_err();
+        assert!(message.contains("1 mismatches"));
+        assert!(message.contains("(1, 0): expected \"  \", found \"##\""));
+    }
+
+    #[test]
+    fn reports_legend_mismatch() {
+        let canvas = sample_canvas();
+        let legend = Legend::new()
+            .with_color('～', UnitColor::Cyan)
+            .with_layer('#', 2);
+        let message = check_canvas_matches(&canvas, "####\n    ～", Some(&legend)).unwrap_err();
+        assert!(message.contains("(2, 1) \"～\": expected color Cyan, found Blue"));
+        assert!(message.contains("(0, 0) \"##\": expected layer 2, found 1"));
+        assert!(message.contains("3 mismatches"));
+    }
+
+    #[test]
+    fn reports_oversized_pattern() {
+        let canvas = sample_canvas();
+        let message = check_canvas_matches(&canvas, "########\n    ～\n", None).unwrap_err();
+        assert!(message.contains("row 0: pattern has 4 units"));
+        let message = check_canvas_matches(&canvas, "####\n    ～\n##", None).unwrap_err();
+        assert!(message.contains("pattern has 3 rows"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn panics_on_mismatch() {
+        assert_canvas_matches(&sample_canvas(), "", None);
+    }
+
+    #[test]
+    fn test_rendered_canvas() {
+        let rendered = RenderedCanvas::from_canvas(&sample_canvas());
+        assert_eq!("####  \n    ～", rendered.to_string());
+        assert_eq!(
+            Some(1),
+            rendered
+                .cell(CanvasItemPosition::new(1, 0))
+                .map(|(_, layer)| layer)
+        );
+        assert_eq!(None, rendered.cell(CanvasItemPosition::new(0, 1)));
+    }
+
+    #[test]
+    fn test_string_without_style() {
+        let units = DrawableUnit::create_units_from("あaい", UnitColor::White);
+        assert_eq!("あa い", string_without_style(&units));
+    }
+}
@@ -0,0 +1,464 @@
+//! エスケープシーケンスを含む出力を解釈し，端末の画面を再現する簡易的なVT100エミュレータを提供する．
+
+extern crate unicode_width;
+
+use crate::{TerminalLattice, UnitAttributes, UnitColor, UnitStyle};
+use data_structure::Pair;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// 端末の画面上の1つの文字セル．
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenCell {
+    /// セルに表示されている文字．全角文字の右半分のセルでは`None`となる．
+    pub c: Option<char>,
+    /// セルに適用されているスタイル．
+    pub style: UnitStyle,
+}
+
+/// 出力されたバイト列を解釈し，画面の内容を再現する仮想端末．
+///
+/// 以下の制御文字およびエスケープシーケンスに対応する．その他のシーケンスは無視される．
+/// 1. `\n` (復帰を伴う改行)，`\r`
+/// 1. CSI `H`/`f` (カーソル位置指定)，`A`/`B`/`C`/`D` (カーソル移動)
+/// 1. CSI `J`/`K` (画面および行の消去)
+/// 1. CSI `m` (SGR: 16色，256色，RGB色および文字装飾)
+/// 1. CSI `?25h`/`?25l` (カーソルの表示切り替え)，CSI `?1049h`/`?1049l` (代替スクリーンの切り替え)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VirtualTerminal {
+    /// 画面のサイズ (列数，行数)．
+    size: Pair<TerminalLattice>,
+    /// 画面の各セル．行優先で格納される．
+    cells: Vec<ScreenCell>,
+    /// カーソルの位置 (0始まり)．
+    cursor: Pair<TerminalLattice>,
+    /// 次に書き込まれる文字に適用されるスタイル．
+    style: UnitStyle,
+    /// カーソルが表示されているか．
+    is_cursor_visible: bool,
+    /// 代替スクリーンが有効であるか．
+    is_alternate_screen: bool,
+}
+
+impl ScreenCell {
+    /// スタイルを持たない空白のセル．
+    const BLANK: ScreenCell = ScreenCell {
+        c: Some(' '),
+        style: UnitStyle::plain(),
+    };
+}
+
+impl VirtualTerminal {
+    /// 指定した列数および行数の，空白で埋められた画面を持つ仮想端末を返す．
+    pub fn new(columns: TerminalLattice, rows: TerminalLattice) -> Self {
+        Self {
+            size: Pair::new(columns, rows),
+            cells: vec![ScreenCell::BLANK; columns * rows],
+            cursor: Pair::new(0, 0),
+            style: UnitStyle::plain(),
+            is_cursor_visible: true,
+            is_alternate_screen: false,
+        }
+    }
+
+    /// 画面のサイズ (列数，行数)を返す．
+    pub fn size(&self) -> Pair<TerminalLattice> {
+        self.size
+    }
+
+    /// カーソルの位置 (0始まり)を返す．
+    pub fn cursor(&self) -> Pair<TerminalLattice> {
+        self.cursor
+    }
+
+    /// 次に書き込まれる文字に適用されるスタイルを返す．
+    /// 出力の末尾でスタイルがリセットされているかの検査に用いる．
+    pub fn current_style(&self) -> UnitStyle {
+        self.style
+    }
+
+    /// カーソルが表示されているか返す．
+    pub fn is_cursor_visible(&self) -> bool {
+        self.is_cursor_visible
+    }
+
+    /// 代替スクリーンが有効であるか返す．
+    pub fn is_alternate_screen(&self) -> bool {
+        self.is_alternate_screen
+    }
+
+    /// 指定したセルを返す．画面外のセルについては`None`を返す．
+    pub fn cell(&self, position: Pair<TerminalLattice>) -> Option<ScreenCell> {
+        if position.x < self.size.x && position.y < self.size.y {
+            Some(self.cells[position.y * self.size.x + position.x])
+        } else {
+            None
+        }
+    }
+
+    /// 指定した行に表示されている文字列を返す．
+    pub fn row_text(&self, row: TerminalLattice) -> String {
+        (0..self.size.x)
+            .filter_map(|x| self.cell(Pair::new(x, row)).and_then(|cell| cell.c))
+            .collect()
+    }
+
+    /// 画面全体に表示されている文字列を返す．各行は`\n`で区切られる．
+    pub fn text(&self) -> String {
+        (0..self.size.y)
+            .map(|y| self.row_text(y))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// バイト列を端末に出力したものとして解釈する．
+    /// UTF-8として不正なバイトは置換文字として扱われる．
+    pub fn feed_bytes(&mut self, bytes: &[u8]) {
+        self.feed(&String::from_utf8_lossy(bytes));
+    }
+
+    /// 文字列を端末に出力したものとして解釈する．
+    pub fn feed(&mut self, s: &str) {
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => self.process_escape(&mut chars),
+                '\n' => self.line_feed(),
+                '\r' => self.cursor.x = 0,
+                c if c.is_control() => {}
+                c => self.put_char(c),
+            }
+        }
+    }
+
+    /// ESCに続くシーケンスを解釈する．CSIシーケンス以外は無視される．
+    fn process_escape(&mut self, chars: &mut Peekable<Chars<'_>>) {
+        if chars.peek() != Some(&'[') {
+            chars.next();
+            return;
+        }
+        chars.next();
+        let is_private = chars.peek() == Some(&'?');
+        if is_private {
+            chars.next();
+        }
+        let mut parameters = String::new();
+        let mut final_char = None;
+        for c in chars {
+            if ('@'..='~').contains(&c) {
+                final_char = Some(c);
+                break;
+            }
+            parameters.push(c);
+        }
+        let parameters = parameters
+            .split(';')
+            .map(|p| p.parse::<usize>().ok())
+            .collect::<Vec<_>>();
+        let parameter = |index: usize, default: usize| {
+            parameters.get(index).cloned().flatten().unwrap_or(default)
+        };
+        match (is_private, final_char) {
+            (true, Some(c)) if c == 'h' || c == 'l' => {
+                let is_set = c == 'h';
+                match parameter(0, 0) {
+                    25 => self.is_cursor_visible = is_set,
+                    1049 => {
+                        self.is_alternate_screen = is_set;
+                        self.clear_range(0, self.cells.len());
+                    }
+                    _ => {}
+                }
+            }
+            (true, _) => {}
+            (false, Some('H')) | (false, Some('f')) => {
+                let row = parameter(0, 1).max(1) - 1;
+                let column = parameter(1, 1).max(1) - 1;
+                self.cursor = Pair::new(
+                    column.min(self.size.x.saturating_sub(1)),
+                    row.min(self.size.y.saturating_sub(1)),
+                );
+            }
+            (false, Some('A')) => {
+                self.cursor.y = self.cursor.y.saturating_sub(parameter(0, 1).max(1))
+            }
+            (false, Some('B')) => {
+                self.cursor.y =
+                    (self.cursor.y + parameter(0, 1).max(1)).min(self.size.y.saturating_sub(1))
+            }
+            (false, Some('C')) => {
+                self.cursor.x =
+                    (self.cursor.x + parameter(0, 1).max(1)).min(self.size.x.saturating_sub(1))
+            }
+            (false, Some('D')) => {
+                self.cursor.x = self.cursor.x.saturating_sub(parameter(0, 1).max(1))
+            }
+            (false, Some('J')) => {
+                let cursor_index = self.cursor_index();
+                match parameter(0, 0) {
+                    0 => self.clear_range(cursor_index, self.cells.len()),
+                    1 => self.clear_range(0, cursor_index + 1),
+                    _ => self.clear_range(0, self.cells.len()),
+                }
+            }
+            (false, Some('K')) => {
+                let line_start = self.cursor.y * self.size.x;
+                let cursor_index = self.cursor_index();
+                match parameter(0, 0) {
+                    0 => self.clear_range(cursor_index, line_start + self.size.x),
+                    1 => self.clear_range(line_start, cursor_index + 1),
+                    _ => self.clear_range(line_start, line_start + self.size.x),
+                }
+            }
+            (false, Some('m')) => self.select_graphic_rendition(&parameters),
+            _ => {}
+        }
+    }
+
+    /// SGRシーケンスのパラメータを解釈し，現在のスタイルを更新する．
+    fn select_graphic_rendition(&mut self, parameters: &[Option<usize>]) {
+        let mut parameters = parameters.iter().map(|p| p.unwrap_or(0));
+        while let Some(parameter) = parameters.next() {
+            let style = &mut self.style;
+            match parameter {
+                0 => *style = UnitStyle::plain(),
+                1 => style.attributes |= UnitAttributes::BOLD,
+                2 => style.attributes |= UnitAttributes::DIM,
+                4 => style.attributes |= UnitAttributes::UNDERLINE,
+                5 => style.attributes |= UnitAttributes::BLINK,
+                7 => style.attributes |= UnitAttributes::REVERSE,
+                22 => {
+                    style.attributes = style
+                        .attributes
+                        .difference(UnitAttributes::BOLD | UnitAttributes::DIM)
+                }
+                24 => style.attributes = style.attributes.difference(UnitAttributes::UNDERLINE),
+                25 => style.attributes = style.attributes.difference(UnitAttributes::BLINK),
+                27 => style.attributes = style.attributes.difference(UnitAttributes::REVERSE),
+                30..=37 => {
+                    style.foreground = Some(UnitColor::from_ansi16_index((parameter - 30) as u8))
+                }
+                90..=97 => {
+                    style.foreground =
+                        Some(UnitColor::from_ansi16_index((parameter - 90 + 8) as u8))
+                }
+                38 => style.foreground = Self::extended_color(&mut parameters),
+                39 => style.foreground = None,
+                40..=47 => {
+                    style.background = Some(UnitColor::from_ansi16_index((parameter - 40) as u8))
+                }
+                100..=107 => {
+                    style.background =
+                        Some(UnitColor::from_ansi16_index((parameter - 100 + 8) as u8))
+                }
+                48 => style.background = Self::extended_color(&mut parameters),
+                49 => style.background = None,
+                _ => {}
+            }
+        }
+    }
+
+    /// SGRパラメータ38および48に続く，256色またはRGB色の指定を解釈する．
+    fn extended_color<I: Iterator<Item = usize>>(parameters: &mut I) -> Option<UnitColor> {
+        let mut component = || parameters.next().map(|p| p.min(255) as u8);
+        match component() {
+            Some(5) => component().map(UnitColor::Ansi256),
+            Some(2) => {
+                let r = component()?;
+                let g = component()?;
+                let b = component()?;
+                Some(UnitColor::Rgb(r, g, b))
+            }
+            _ => None,
+        }
+    }
+
+    /// 現在のカーソル位置に文字を書き込み，カーソルを文字幅だけ進める．
+    /// 行末を超える場合は，次の行の先頭に折り返す．
+    fn put_char(&mut self, c: char) {
+        let width = unicode_width::UnicodeWidthChar::width(c).unwrap_or(0);
+        if width == 0 || self.size.x == 0 || self.size.y == 0 {
+            return;
+        }
+        if self.cursor.x + width > self.size.x {
+            self.line_feed();
+        }
+        let index = self.cursor_index();
+        self.cells[index] = ScreenCell {
+            c: Some(c),
+            style: self.style,
+        };
+        if width == 2 && self.cursor.x + 1 < self.size.x {
+            self.cells[index + 1] = ScreenCell {
+                c: None,
+                style: self.style,
+            };
+        }
+        self.cursor.x += width;
+        if self.cursor.x >= self.size.x {
+            // 行末に達した場合は，次の文字を書き込む際に折り返す
+            self.cursor.x = self.size.x;
+        }
+    }
+
+    /// カーソルを次の行の先頭に移動する．最終行の場合は画面全体を1行上にスクロールする．
+    fn line_feed(&mut self) {
+        self.cursor.x = 0;
+        if self.size.y == 0 {
+            return;
+        }
+        if self.cursor.y + 1 < self.size.y {
+            self.cursor.y += 1;
+        } else {
+            self.cells.drain(0..self.size.x);
+            self.cells
+                .resize(self.size.x * self.size.y, ScreenCell::BLANK);
+        }
+    }
+
+    /// 指定した範囲のセルを空白にする．
+    fn clear_range(&mut self, start: usize, end: usize) {
+        let end = end.min(self.cells.len());
+        for cell in self.cells[start.min(end)..end].iter_mut() {
+            *cell = ScreenCell::BLANK;
+        }
+    }
+
+    /// カーソル位置のセルの，`cells`内での位置を返す．
+    fn cursor_index(&self) -> usize {
+        let x = self.cursor.x.min(self.size.x.saturating_sub(1));
+        self.cursor.y * self.size.x + x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Canvas, CanvasItemPosition, ColorSupport, DiffRenderer, DrawableUnit, FrameWriter,
+    };
+
+    #[test]
+    fn cursor_and_clear() {
+        let mut terminal = VirtualTerminal::new(6, 3);
+        terminal.feed("abc\ndef\x1b[1;2HX\x1b[3;5Hあ");
+        assert_eq!("aXc   \ndef   \n    あ", terminal.text());
+        terminal.feed("\x1b[2;2H\x1b[K");
+        assert_eq!("d     ", terminal.row_text(1));
+        terminal.feed("\x1b[2J");
+        assert_eq!("      \n      \n      ", terminal.text());
+    }
+
+    #[test]
+    fn wrap_and_scroll() {
+        let mut terminal = VirtualTerminal::new(3, 2);
+        terminal.feed("abcdefg");
+        assert_eq!("def\ng  ", terminal.text());
+    }
+
+    #[test]
+    fn sgr() {
+        let mut terminal = VirtualTerminal::new(4, 1);
+        terminal.feed("\x1b[0;31;44;1ma\x1b[38;5;208;48;2;1;2;3;22mb\x1b[0mc");
+        let style = |x| terminal.cell(Pair::new(x, 0)).unwrap().style;
+        assert_eq!(
+            UnitStyle {
+                foreground: Some(UnitColor::Red),
+                background: Some(UnitColor::Blue),
+                attributes: UnitAttributes::BOLD,
+            },
+            style(0)
+        );
+        assert_eq!(
+            UnitStyle {
+                foreground: Some(UnitColor::Ansi256(208)),
+                background: Some(UnitColor::Rgb(1, 2, 3)),
+                attributes: UnitAttributes::NONE,
+            },
+            style(1)
+        );
+        assert_eq!(UnitStyle::plain(), style(2));
+        assert!(terminal.current_style().is_plain());
+    }
+
+    #[test]
+    fn canvas_output_does_not_leak_style() {
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        let unit = DrawableUnit::from_double_half_char('a', 'b', UnitColor::Red)
+            .with_background(UnitColor::Blue);
+        canvas.draw_unit(unit, CanvasItemPosition::new(2, 1), 0);
+        let mut output = String::new();
+        canvas
+            .write_with_color_support_to(&mut output, ColorSupport::TrueColor)
+            .unwrap();
+        let mut terminal = VirtualTerminal::new(12, 4);
+        terminal.feed(&output);
+        assert!(terminal.current_style().is_plain());
+        assert_eq!(" |    ab|   ", terminal.row_text(2));
+        // 描画単位の色が実際に端末へ反映されている
+        let style = terminal.cell(Pair::new(6, 2)).map(|cell| cell.style);
+        assert_eq!(Some(Some(UnitColor::Red)), style.map(|s| s.foreground));
+        assert_eq!(Some(Some(UnitColor::Blue)), style.map(|s| s.background));
+        assert_eq!(Some(unit.style()), style);
+        // 行末以降にスタイルが漏れない
+        assert_eq!(
+            Some(UnitStyle::plain()),
+            terminal.cell(Pair::new(10, 2)).map(|cell| cell.style)
+        );
+    }
+
+    #[test]
+    fn incremental_updates_converge_to_full_redraw() {
+        // 実行環境に依存せず色が書き込まれるよう，色表現範囲を固定する
+        let color_support = ColorSupport::TrueColor;
+        let mut canvas = Canvas::with_size(Pair::new(4, 3));
+        let mut renderer = DiffRenderer::new();
+        let mut incremental = VirtualTerminal::new(12, 5);
+        let frames: [&[(usize, usize, char, UnitColor)]; 3] = [
+            &[(0, 0, 'a', UnitColor::Red), (3, 2, 'b', UnitColor::Green)],
+            &[
+                (1, 1, 'c', UnitColor::Ansi256(100)),
+                (2, 1, 'd', UnitColor::Blue),
+            ],
+            &[(3, 2, 'e', UnitColor::Rgb(10, 20, 30))],
+        ];
+        for frame in frames.iter() {
+            canvas.clear();
+            for &(x, y, c, color) in frame.iter() {
+                let unit = DrawableUnit::from_double_half_char(c, c, color);
+                canvas.draw_unit(unit, CanvasItemPosition::new(x, y), 0);
+            }
+            let mut writer = FrameWriter::with_color_support(Vec::new(), color_support);
+            writer.render(&mut renderer, &canvas).unwrap();
+            incremental.feed_bytes(writer.get_ref());
+
+            let mut full = VirtualTerminal::new(12, 5);
+            let mut output = String::new();
+            canvas
+                .write_with_color_support_to(&mut output, color_support)
+                .unwrap();
+            full.feed(&output);
+            assert_eq!(full.text(), incremental.text());
+            // 描画した各点には，既定でない文字色が表示されている
+            let origin = canvas.content_origin();
+            for &(x, y, _, color) in frame.iter() {
+                let position = Pair::new(origin.x + x * 2, origin.y + y);
+                assert_eq!(
+                    Some(Some(color)),
+                    incremental.cell(position).map(|cell| cell.style.foreground)
+                );
+            }
+            for y in 0..5 {
+                for x in 0..12 {
+                    assert_eq!(
+                        full.cell(Pair::new(x, y)),
+                        incremental.cell(Pair::new(x, y)),
+                        "cell ({}, {})",
+                        x,
+                        y
+                    );
+                }
+            }
+        }
+    }
+}
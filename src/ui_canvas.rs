@@ -1,21 +1,366 @@
-use crate::{Canvas, Layer};
-use crate::DrawableUnit;
+use crate::{Canvas, CanvasItemPosition, CanvasLattice, DrawError, DrawableUnit, Layer};
 use data_structure::Pair;
+use geometry::Rectangle;
 
 pub type UiLattice = usize;
 pub type UiPosition = Pair<UiLattice>;
 
+/// UIの描画を許可する領域．右端および下端の座標は領域に含まれない．
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ClipRegion {
+    left: UiLattice,
+    top: UiLattice,
+    right: UiLattice,
+    bottom: UiLattice,
+}
+
+/// UIの情報を描画する．
+/// キャンバス外の点や，クリップ領域外の点への描画は無視される．
+///
+/// 座標はこのキャンバスの原点を基準とする．`sub_panel`で生成したパネルでは，パネルの左上の点が原点となる．
 pub struct UiCanvas<'a, L> {
     canvas: &'a mut Canvas<L>,
+    /// このキャンバスの原点に対応する，描画先キャンバス上の点．
+    origin: UiPosition,
+    /// 取り除くことのできないクリップ領域 (描画先キャンバス上の座標)．パネルの領域がこれにあたる．
+    base_clip: Option<ClipRegion>,
+    /// `push_clip`で指定されたクリップ領域 (描画先キャンバス上の座標)．末尾の領域が現在のクリップ領域となる．
+    /// 各領域は，それ以前に指定された領域との共通部分として保持される．
+    clip_stack: Vec<ClipRegion>,
+}
+
+impl ClipRegion {
+    /// 指定した矩形 (両端の点を含む)と同じ領域を返す．
+    /// 右端または下端が座標の最大値に達する場合，その列または行は領域に含まれない．
+    fn from_rectangle(rectangle: Rectangle<UiLattice>) -> Self {
+        Self {
+            left: rectangle.left(),
+            top: rectangle.top(),
+            right: rectangle.right().saturating_add(1),
+            bottom: rectangle.bottom().saturating_add(1),
+        }
+    }
+
+    /// この領域を指定した量だけ平行移動した領域を返す．
+    /// 座標の最大値を超える部分は，最大値に切り詰められる．
+    fn offset(&self, offset: UiPosition) -> Self {
+        Self {
+            left: self.left.saturating_add(offset.x),
+            top: self.top.saturating_add(offset.y),
+            right: self.right.saturating_add(offset.x),
+            bottom: self.bottom.saturating_add(offset.y),
+        }
+    }
+
+    /// 2つの領域の共通部分を返す．共通部分がない場合は，空の領域を返す．
+    fn intersection(&self, other: &Self) -> Self {
+        use std::cmp::{max, min};
+        let left = max(self.left, other.left);
+        let top = max(self.top, other.top);
+        Self {
+            left,
+            top,
+            right: max(left, min(self.right, other.right)),
+            bottom: max(top, min(self.bottom, other.bottom)),
+        }
+    }
+
+    /// 指定した点がこの領域内にあるか返す．
+    fn contains(&self, position: UiPosition) -> bool {
+        (self.left..self.right).contains(&position.x)
+            && (self.top..self.bottom).contains(&position.y)
+    }
 }
 
 impl<'a, L: Layer> UiCanvas<'a, L> {
     pub fn from_canvas(canvas: &'a mut Canvas<L>) -> Self {
-        Self { canvas }
+        Self {
+            canvas,
+            origin: UiPosition::new(0, 0),
+            base_clip: None,
+            clip_stack: vec![],
+        }
     }
 
+    /// このキャンバスの原点から，描画可能な領域の右端および下端までの大きさを返す．
+    /// `sub_panel`で生成したパネルではパネルの幅および高さ，クリップ領域が存在する場合はその右端および下端までの大きさとなる．
+    /// いずれの場合も，描画先キャンバスの外側は含まない．
+    pub fn size(&self) -> Pair<CanvasLattice> {
+        let canvas_size = self.canvas.size();
+        let (right, bottom) = match self.current_clip() {
+            Some(clip) => (
+                clip.right.min(canvas_size.x),
+                clip.bottom.min(canvas_size.y),
+            ),
+            None => (canvas_size.x, canvas_size.y),
+        };
+        Pair::new(
+            right.saturating_sub(self.origin.x),
+            bottom.saturating_sub(self.origin.y),
+        )
+    }
+
+    /// このキャンバスの指定した領域 (両端の点を含む)を，新たなUIキャンバスとして返す．
+    /// 返されるキャンバスでは領域の左上の点が原点となり，領域外および現在のクリップ領域外への描画は無視される．
+    /// パネルはさらに入れ子にできる．
+    pub fn sub_panel(&mut self, region: Rectangle<UiLattice>) -> UiCanvas<'_, L> {
+        let panel = ClipRegion::from_rectangle(region).offset(self.origin);
+        let base_clip = match self.current_clip() {
+            Some(current) => current.intersection(&panel),
+            None => panel,
+        };
+        UiCanvas {
+            canvas: self.canvas,
+            origin: UiPosition::new(panel.left, panel.top),
+            base_clip: Some(base_clip),
+            clip_stack: vec![],
+        }
+    }
+
+    /// クリップ領域を追加する．
+    /// 以後，`pop_clip`で取り除かれるまで，指定した領域 (両端の点を含む)と現在のクリップ領域との共通部分の外側への描画は無視される．
+    pub fn push_clip(&mut self, region: Rectangle<UiLattice>) {
+        let region = ClipRegion::from_rectangle(region).offset(self.origin);
+        let region = match self.current_clip() {
+            Some(current) => current.intersection(&region),
+            None => region,
+        };
+        self.clip_stack.push(region);
+    }
+
+    /// 最後に追加したクリップ領域を取り除く．
+    /// `sub_panel`によるパネルの領域は取り除かれない．
+    /// # Returns
+    /// 取り除くクリップ領域が存在しなかった場合は`false`を返す．
+    pub fn pop_clip(&mut self) -> bool {
+        self.clip_stack.pop().is_some()
+    }
+
+    /// オブジェクトを描画する．
+    /// キャンバス外の点や，クリップ領域外の点への描画は無視される．
     pub fn draw_unit(&mut self, drawable_unit: DrawableUnit, ui_position: UiPosition, layer: L) {
-        let canvas_position = ui_position.into();
-        self.canvas.draw_unit(drawable_unit, canvas_position, layer)
+        if let Some(canvas_position) = self.canvas_position_of(ui_position) {
+            self.canvas.draw_unit(drawable_unit, canvas_position, layer)
+        }
+    }
+
+    /// オブジェクトを描画する．
+    /// キャンバス外の点や，クリップ領域外の点への描画は，`draw_unit`と同様に無視される．
+    /// # Returns
+    /// 描画単位がコンソール上の最小の正方形領域に収まらない場合は，エラーを返す．
+    pub fn try_draw_unit(
+        &mut self,
+        drawable_unit: DrawableUnit,
+        ui_position: UiPosition,
+        layer: L,
+    ) -> Result<(), DrawError> {
+        match self.canvas_position_of(ui_position) {
+            Some(canvas_position) => {
+                self.canvas
+                    .try_draw_unit(drawable_unit, canvas_position, layer)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// 現在のクリップ領域を返す．クリップ領域が存在しない場合は`None`を返す．
+    fn current_clip(&self) -> Option<&ClipRegion> {
+        self.clip_stack.last().or(self.base_clip.as_ref())
+    }
+
+    /// UI上の指定した点を描画する場合の，キャンバス上の描画先となる点を返す．
+    /// 描画先がキャンバス外またはクリップ領域外となる場合，および座標が桁あふれする場合は`None`を返す．
+    fn canvas_position_of(&self, ui_position: UiPosition) -> Option<CanvasItemPosition> {
+        let canvas_position = CanvasItemPosition::new(
+            self.origin.x.checked_add(ui_position.x)?,
+            self.origin.y.checked_add(ui_position.y)?,
+        );
+        let is_inside_clip = match self.current_clip() {
+            Some(clip) => clip.contains(canvas_position),
+            None => true,
+        };
+        if is_inside_clip && self.canvas.is_drawable_at(canvas_position) {
+            Some(canvas_position)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UnitColor;
+
+    fn unit() -> DrawableUnit {
+        DrawableUnit::from_double_half_char('a', 'a', UnitColor::White)
+    }
+
+    #[test]
+    fn outside_of_canvas_is_ignored() {
+        let mut canvas = Canvas::with_size(Pair::new(3, 2));
+        let mut ui_canvas = UiCanvas::from_canvas(&mut canvas);
+        ui_canvas.draw_unit(unit(), UiPosition::new(3, 0), 0);
+        ui_canvas.draw_unit(unit(), UiPosition::new(0, 2), 0);
+        assert!(ui_canvas
+            .try_draw_unit(unit(), UiPosition::new(10, 10), 0)
+            .is_ok());
+        ui_canvas.draw_unit(unit(), UiPosition::new(2, 1), 0);
+        assert_eq!(
+            vec![CanvasItemPosition::new(2, 1)],
+            canvas.units().map(|(p, _, _)| p).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn clip_stack() {
+        let mut canvas = Canvas::with_size(Pair::new(5, 5));
+        let mut ui_canvas = UiCanvas::from_canvas(&mut canvas);
+        ui_canvas.push_clip(Rectangle::from_corners(
+            UiPosition::new(1, 1),
+            UiPosition::new(3, 3),
+        ));
+        ui_canvas.push_clip(Rectangle::from_corners(
+            UiPosition::new(2, 0),
+            UiPosition::new(4, 2),
+        ));
+        // 共通部分 (2, 1)-(3, 2)のみ描画可能
+        for y in 0..5 {
+            for x in 0..5 {
+                ui_canvas.draw_unit(unit(), UiPosition::new(x, y), 0);
+            }
+        }
+        assert!(ui_canvas.pop_clip());
+        ui_canvas.draw_unit(unit(), UiPosition::new(1, 3), 0);
+        ui_canvas.draw_unit(unit(), UiPosition::new(4, 4), 0);
+        assert!(ui_canvas.pop_clip());
+        assert!(!ui_canvas.pop_clip());
+        ui_canvas.draw_unit(unit(), UiPosition::new(0, 4), 0);
+        assert_eq!(
+            vec![
+                CanvasItemPosition::new(2, 1),
+                CanvasItemPosition::new(3, 1),
+                CanvasItemPosition::new(2, 2),
+                CanvasItemPosition::new(3, 2),
+                CanvasItemPosition::new(1, 3),
+                CanvasItemPosition::new(0, 4),
+            ],
+            canvas.units().map(|(p, _, _)| p).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn clip_touching_max_position() {
+        let mut canvas = Canvas::with_size(Pair::new(3, 3));
+        let mut ui_canvas = UiCanvas::from_canvas(&mut canvas);
+        ui_canvas.push_clip(Rectangle::from_corners(
+            UiPosition::new(1, 1),
+            UiPosition::new(UiLattice::MAX, UiLattice::MAX),
+        ));
+        ui_canvas.draw_unit(unit(), UiPosition::new(0, 0), 0);
+        ui_canvas.draw_unit(unit(), UiPosition::new(2, 2), 0);
+        assert_eq!(
+            vec![CanvasItemPosition::new(2, 2)],
+            canvas.units().map(|(p, _, _)| p).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn nested_sub_panels() {
+        let mut canvas = Canvas::with_size(Pair::new(10, 10));
+        let mut ui_canvas = UiCanvas::from_canvas(&mut canvas);
+        {
+            let mut panel = ui_canvas.sub_panel(Rectangle::from_corners(
+                UiPosition::new(2, 3),
+                UiPosition::new(6, 5),
+            ));
+            // パネルの原点はパネルの左上の点
+            panel.draw_unit(unit(), UiPosition::new(0, 0), 0);
+            // パネル外への描画は無視される
+            panel.draw_unit(unit(), UiPosition::new(5, 0), 0);
+            panel.draw_unit(unit(), UiPosition::new(0, 3), 0);
+            // パネルの領域はpop_clipで取り除かれない
+            assert!(!panel.pop_clip());
+            panel.draw_unit(unit(), UiPosition::new(0, 3), 0);
+            {
+                // 親パネルからはみ出す部分はクリップされる
+                let mut nested = panel.sub_panel(Rectangle::from_corners(
+                    UiPosition::new(3, 1),
+                    UiPosition::new(8, 8),
+                ));
+                nested.draw_unit(unit(), UiPosition::new(1, 1), 0);
+                nested.draw_unit(unit(), UiPosition::new(2, 1), 0);
+                nested.draw_unit(unit(), UiPosition::new(1, 2), 0);
+            }
+            panel.push_clip(Rectangle::from_corners(
+                UiPosition::new(1, 0),
+                UiPosition::new(1, 0),
+            ));
+            panel.draw_unit(unit(), UiPosition::new(1, 0), 0);
+            panel.draw_unit(unit(), UiPosition::new(2, 0), 0);
+        }
+        assert_eq!(
+            vec![
+                CanvasItemPosition::new(2, 3),
+                CanvasItemPosition::new(3, 3),
+                CanvasItemPosition::new(6, 5),
+            ],
+            canvas.units().map(|(p, _, _)| p).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn overflowing_position_in_sub_panel_is_ignored() {
+        let mut canvas = Canvas::with_size(Pair::new(5, 5));
+        let mut ui_canvas = UiCanvas::from_canvas(&mut canvas);
+        let mut panel = ui_canvas.sub_panel(Rectangle::from_corners(
+            UiPosition::new(1, 1),
+            UiPosition::new(3, 3),
+        ));
+        panel.draw_unit(unit(), UiPosition::new(UiLattice::MAX, 0), 0);
+        panel.draw_unit(unit(), UiPosition::new(0, UiLattice::MAX), 0);
+        assert!(panel
+            .try_draw_unit(unit(), UiPosition::new(UiLattice::MAX, 0), 0)
+            .is_ok());
+        {
+            // 親パネルの原点からさらに平行移動したパネル
+            let mut nested = panel.sub_panel(Rectangle::from_corners(
+                UiPosition::new(1, 1),
+                UiPosition::new(UiLattice::MAX, UiLattice::MAX),
+            ));
+            nested.draw_unit(unit(), UiPosition::new(UiLattice::MAX, 0), 0);
+            nested.draw_unit(unit(), UiPosition::new(0, 0), 0);
+        }
+        assert_eq!(
+            vec![CanvasItemPosition::new(2, 2)],
+            canvas.units().map(|(p, _, _)| p).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn size_of_sub_panel() {
+        let mut canvas = Canvas::<i32>::with_size(Pair::new(10, 8));
+        let mut ui_canvas = UiCanvas::from_canvas(&mut canvas);
+        assert_eq!(Pair::new(10, 8), ui_canvas.size());
+        let mut panel = ui_canvas.sub_panel(Rectangle::from_corners(
+            UiPosition::new(2, 3),
+            UiPosition::new(6, 5),
+        ));
+        assert_eq!(Pair::new(5, 3), panel.size());
+        {
+            // 親パネルおよびキャンバスからはみ出す部分は含まない
+            let nested = panel.sub_panel(Rectangle::from_corners(
+                UiPosition::new(3, 1),
+                UiPosition::new(20, 20),
+            ));
+            assert_eq!(Pair::new(2, 2), nested.size());
+        }
+        panel.push_clip(Rectangle::from_corners(
+            UiPosition::new(0, 0),
+            UiPosition::new(1, 0),
+        ));
+        assert_eq!(Pair::new(2, 1), panel.size());
+        panel.pop_clip();
+        assert_eq!(Pair::new(5, 3), panel.size());
     }
 }
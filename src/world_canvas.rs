@@ -1,4 +1,4 @@
-use crate::{Canvas, CanvasItemPosition, CanvasLattice, DrawableUnit, Layer};
+use crate::{Canvas, CanvasItemPosition, CanvasLattice, DrawError, DrawableUnit, Layer};
 use data_structure::Pair;
 
 pub type WorldLattice = isize;
@@ -78,6 +78,28 @@ impl<'a, L: Layer> WorldCanvas<'a, L> {
             self.canvas.draw_unit(drawable_unit, canvas_position, layer)
         }
     }
+
+    /// フィールド上のオブジェクトを描画する．
+    /// キャンバス外の点に対応するオブジェクトは，`draw_unit`と同様に描画されずに無視される．
+    /// # Returns
+    /// 描画単位がコンソール上の最小の正方形領域に収まらない場合は，エラーを返す．
+    pub fn try_draw_unit(
+        &mut self,
+        drawable_unit: DrawableUnit,
+        world_position: WorldPosition,
+        layer: L,
+    ) -> Result<(), DrawError> {
+        match self
+            .reference
+            .canvas_position_of(world_position, self.canvas)
+        {
+            Some(canvas_position) => {
+                self.canvas
+                    .try_draw_unit(drawable_unit, canvas_position, layer)
+            }
+            None => Ok(()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -109,4 +131,21 @@ mod reference_tests {
             )
         );
     }
+    #[test]
+    fn test_canvas_position_of_with_sized_canvas() {
+        let canvas = Canvas::<i32>::with_size(CanvasItemPosition::new(4, 3));
+        let reference = Reference::new(CanvasItemPosition::new(0, 0), WorldPosition::new(0, 0));
+        assert_eq!(
+            Some(CanvasItemPosition::new(3, 2)),
+            reference.canvas_position_of(WorldPosition::new(3, 2), &canvas)
+        );
+        assert_eq!(
+            None,
+            reference.canvas_position_of(WorldPosition::new(4, 2), &canvas)
+        );
+        assert_eq!(
+            None,
+            reference.canvas_position_of(WorldPosition::new(3, 3), &canvas)
+        );
+    }
 }